@@ -0,0 +1,726 @@
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::bots::{Bot, Strategy};
+use crate::error::DraftError;
+use crate::{add_player_to_captain, BidRecord, Captain, Config, DraftState, Player};
+
+/// Everything one auction runs on. The real draft and a mock draft each get
+/// their own, so a mock never touches the real pool or rosters
+pub struct Session {
+    pub captains: Mutex<Vec<Arc<Mutex<Captain>>>>,
+    pub players: Mutex<Vec<Arc<Mutex<Player>>>>,
+    pub draft_state: Mutex<DraftState>,
+    pub bots: Mutex<Vec<Bot>>,
+    pub mock: bool,
+}
+
+impl Session {
+    pub fn new(players: Vec<Arc<Mutex<Player>>>) -> Self {
+        Self {
+            captains: Mutex::new(Vec::new()),
+            players: Mutex::new(players),
+            draft_state: Mutex::new(DraftState::new()),
+            bots: Mutex::new(Vec::new()),
+            mock: false,
+        }
+    }
+
+    /// Copies the captains, rosters and pool of `real` for a mock draft.
+    /// Captains whose discord id is in `humans` play themselves, the rest are
+    /// bots using `strategy`, or a mix of strategies when it is None
+    pub async fn mock_copy(real: &Session, humans: &[u64], strategy: Option<Strategy>, seed: u64) -> Self {
+        let mut captains = Vec::new();
+        let mut captain_map = Vec::new();
+        for captain in real.captains.lock().await.iter() {
+            let c1 = captain.lock().await;
+            let copy = Arc::new(Mutex::new(Captain::new(c1.discord_id, c1.name.clone(), c1.balance)));
+            captain_map.push((captain.clone(), copy.clone()));
+            captains.push(copy);
+        }
+        let mut players = Vec::new();
+        for player in real.players.lock().await.iter() {
+            let p1 = player.lock().await;
+            let mut copy = Player::new(p1.name.clone(), p1.tags.clone());
+            copy.discord_id = p1.discord_id;
+            copy.clan = p1.clan.clone();
+            copy.overall_wn8 = p1.overall_wn8;
+            copy.recent_wn8 = p1.recent_wn8;
+            copy.notes = p1.notes.clone();
+            copy.tier = p1.tier.clone();
+            copy.picked = p1.picked;
+            copy.purchase = p1.purchase;
+            let team = p1.team.as_ref()
+                .and_then(|t| captain_map.iter().find(|(real, _)| Arc::ptr_eq(real, t)))
+                .map(|(_, copy)| copy.clone());
+            drop(p1);
+            let copy = Arc::new(Mutex::new(copy));
+            if let Some(team) = team {
+                let mut c1 = team.lock().await;
+                c1.count_player(&*copy.lock().await);
+                c1.players.push(copy.clone());
+                copy.lock().await.team = Some(team.clone());
+            }
+            players.push(copy);
+        }
+        let mut bots = Vec::new();
+        for (i, captain) in captains.iter().enumerate() {
+            if humans.contains(&captain.lock().await.discord_id) {
+                continue;
+            }
+            let strategy = strategy.unwrap_or(Strategy::ALL[i % Strategy::ALL.len()]);
+            bots.push(Bot::new(captain.clone(), strategy, seed.wrapping_add(i as u64)));
+        }
+        Self {
+            captains: Mutex::new(captains),
+            players: Mutex::new(players),
+            draft_state: Mutex::new(DraftState::new()),
+            bots: Mutex::new(bots),
+            mock: true,
+        }
+    }
+
+    pub async fn find_captain_by_name(&self, name: &str) -> Option<Arc<Mutex<Captain>>> {
+        for c_lock in self.captains.lock().await.iter() {
+            if c_lock.lock().await.name.eq_ignore_ascii_case(name) {
+                return Some(c_lock.clone());
+            }
+        }
+        None
+    }
+
+    pub async fn find_captain(&self, discord_id: u64) -> Option<Arc<Mutex<Captain>>> {
+        for c_lock in self.captains.lock().await.iter() {
+            if c_lock.lock().await.discord_id == discord_id {
+                return Some(c_lock.clone());
+            }
+        }
+        None
+    }
+
+    /// Unpicked player with this name ignoring case, or why there is none
+    pub async fn nominee(&self, name: &str) -> Result<Arc<Mutex<Player>>, DraftError> {
+        for p_lock in self.players.lock().await.iter() {
+            let p = p_lock.lock().await;
+            if !p.name.eq_ignore_ascii_case(name) {
+                continue;
+            }
+            if p.picked {
+                return Err(DraftError::PlayerUnavailable(p.name.clone()));
+            }
+            return Ok(p_lock.clone());
+        }
+        Err(DraftError::UnknownPlayer(name.to_string()))
+    }
+
+    /// Unpicked player with this name, ignoring case
+    pub async fn find_available(&self, name: &str) -> Option<Arc<Mutex<Player>>> {
+        for p_lock in self.players.lock().await.iter() {
+            let p = p_lock.lock().await;
+            if p.name.eq_ignore_ascii_case(name) && !p.picked {
+                return Some(p_lock.clone());
+            }
+        }
+        None
+    }
+}
+
+/// Adds a captain, returns the announcement. A captain who plays is put on
+/// their own team for `value`, or for their WN8 priced with the captain cost
+pub async fn add_captain(
+    session: &Session,
+    config: &Config,
+    discord_id: u64,
+    name: &str,
+    player: Option<&str>,
+    wn8: Option<u32>,
+    value: Option<u32>,
+) -> Result<String, DraftError> {
+    let mut captain = Captain::new(discord_id, name.to_string(), config.starting_balance);
+    let mut captains = session.captains.lock().await;
+    for captain in captains.iter() {
+        if captain.lock().await.discord_id == discord_id {
+            return Err(DraftError::AlreadyCaptain);
+        }
+    }
+    let plays = player.is_some() || wn8.is_some() || value.is_some();
+    let mut own_player = None;
+    if let Some(player) = player {
+        own_player = Some(session.find_available(player).await
+            .ok_or_else(|| DraftError::PlayerUnavailable(player.to_string()))?);
+    }
+    if !plays {
+        captains.push(Arc::new(Mutex::new(captain)));
+        info!(captain = name, discord_id, "captain added");
+        return Ok(format!("Added captain {}", name));
+    }
+    let own_player = match own_player {
+        Some(p) => p,
+        None => {
+            for p_lock in session.players.lock().await.iter() {
+                if p_lock.lock().await.name.eq_ignore_ascii_case(name) {
+                    return Err(DraftError::CaptainNameTaken(name.to_string()));
+                }
+            }
+            let mut p = Player::new(name.to_string(), Vec::new());
+            p.discord_id = Some(discord_id);
+            Arc::new(Mutex::new(p))
+        }
+    };
+    let mut p = own_player.lock().await;
+    let recent_wn8 = wn8.unwrap_or(p.recent_wn8);
+    let cost = value.unwrap_or(config.captain_cost.cost(recent_wn8));
+    let needed = config.team_size.saturating_sub(1).checked_mul(config.min_bid)
+        .and_then(|rest| rest.checked_add(cost));
+    if needed.is_none_or(|needed| needed > config.starting_balance) {
+        return Err(DraftError::CaptainTooExpensive(cost));
+    }
+    p.recent_wn8 = recent_wn8;
+    drop(p);
+    if player.is_none() {
+        session.players.lock().await.push(own_player.clone());
+    }
+    captain.balance -= cost;
+    let captain = Arc::new(Mutex::new(captain));
+    add_player_to_captain(captain.clone(), own_player, cost, 0).await;
+    captains.push(captain);
+    info!(captain = name, discord_id, cost, "playing captain added");
+    Ok(format!("Added captain {}, playing for ${}", name, cost))
+}
+
+/// Lowest recent WN8 among unpicked players other than `except`, the least
+/// an open roster slot adds under a WN8 cap. Locks players, so call it
+/// before taking any captain lock
+pub async fn wn8_floor(players: &[Arc<Mutex<Player>>], except: Option<&Arc<Mutex<Player>>>) -> u32 {
+    let mut floor: Option<u32> = None;
+    for player in players {
+        if except.is_some_and(|e| Arc::ptr_eq(e, player)) {
+            continue;
+        }
+        let p1 = player.lock().await;
+        if !p1.picked {
+            floor = Some(floor.map_or(p1.recent_wn8, |f| f.min(p1.recent_wn8)));
+        }
+    }
+    floor.unwrap_or(0)
+}
+
+/// Unpicked players `captain` may take and can afford at their minimum
+/// price, with that price
+pub async fn eligible_players(
+    players: &[Arc<Mutex<Player>>],
+    config: &Config,
+    captain: &Arc<Mutex<Captain>>,
+) -> Vec<(Arc<Mutex<Player>>, u32)> {
+    // the two weakest unpicked players give every candidate's floor in one pass
+    let mut pool = Vec::new();
+    for player in players {
+        let p1 = player.lock().await;
+        if !p1.picked {
+            pool.push((player.clone(), p1.recent_wn8));
+        }
+    }
+    let mut by_wn8: Vec<&(Arc<Mutex<Player>>, u32)> = pool.iter().collect();
+    by_wn8.sort_by_key(|(_, wn8)| *wn8);
+    let floor = |player: &Arc<Mutex<Player>>| match by_wn8.as_slice() {
+        [(lowest, _), rest @ ..] if Arc::ptr_eq(lowest, player) => rest.first().map_or(0, |(_, wn8)| *wn8),
+        [(_, wn8), ..] => *wn8,
+        [] => 0,
+    };
+    let mut eligible = Vec::new();
+    let c1 = captain.lock().await;
+    for (player, _) in pool.iter() {
+        let p1 = player.lock().await;
+        if p1.picked {
+            continue;
+        }
+        let min_price = config.min_price(p1.tier.as_deref());
+        if c1.check_player(config, &p1, floor(player)).is_ok() && c1.get_max_bid(config, Some(&p1)) >= min_price {
+            eligible.push((player.clone(), min_price));
+        }
+    }
+    eligible
+}
+
+/// Puts `player` up for auction on behalf of the round's captain, returns
+/// the opening bid. `pick` and the bots both go through here
+pub async fn nominate(
+    session: &Session,
+    config: &Config,
+    captain: &Arc<Mutex<Captain>>,
+    player: &Arc<Mutex<Player>>,
+    starting_bid: Option<u32>,
+) -> Result<u32, DraftError> {
+    let mut draft_state = session.draft_state.lock().await;
+    let Some(round_captain) = draft_state.round_captain.clone() else {
+        return Err(DraftError::NoDraftRunning);
+    };
+    if draft_state.time != config.round_time || draft_state.nominated_player.is_some() {
+        return Err(DraftError::NominationClosed);
+    }
+    if !Arc::ptr_eq(&round_captain, captain) {
+        return Err(DraftError::NotYourTurn);
+    }
+    let floor = wn8_floor(&session.players.lock().await, Some(player)).await;
+    let mut c = captain.lock().await;
+    let p = player.lock().await;
+    if p.picked {
+        return Err(DraftError::PlayerUnavailable(p.name.clone()));
+    }
+    c.check_player(config, &p, floor).map_err(DraftError::RuleViolation)?;
+    let max_bid = c.get_max_bid(config, Some(&p));
+    let min_price = config.min_price(p.tier.as_deref());
+    let final_bid = starting_bid.unwrap_or(min_price);
+    if final_bid < min_price {
+        return Err(DraftError::BelowMinimum { player: p.name.clone(), min_price });
+    }
+    if final_bid > max_bid {
+        return Err(DraftError::InsufficientFunds { max_bid });
+    }
+    draft_state.nominated_player = Some(player.clone());
+    draft_state.current_winner = Some(captain.clone());
+    draft_state.starting_bid = final_bid;
+    draft_state.current_bid = final_bid;
+    c.bid_history.push(BidRecord {
+        round: draft_state.current_round,
+        player: p.name.clone(),
+        amount: final_bid,
+    });
+    info!(captain = %c.name, player = %p.name, amount = final_bid, round = draft_state.current_round, "player nominated");
+    Ok(final_bid)
+}
+
+/// Raises the bid on the nominated player, returns the player's name.
+/// `bid` and the bots both go through here
+pub async fn place_bid(
+    session: &Session,
+    config: &Config,
+    captain: &Arc<Mutex<Captain>>,
+    amount: u32,
+) -> Result<String, DraftError> {
+    let mut draft_state = session.draft_state.lock().await;
+    if draft_state.time == 0 {
+        return Err(DraftError::NoAuctionRunning);
+    }
+    if amount <= draft_state.current_bid {
+        return Err(DraftError::UnderTopBid);
+    }
+    let Some(player) = draft_state.nominated_player.clone() else {
+        return Err(DraftError::NothingNominated);
+    };
+    let floor = wn8_floor(&session.players.lock().await, Some(&player)).await;
+    let mut c = captain.lock().await;
+    let p = player.lock().await;
+    if c.players.len() as u32 >= config.team_size {
+        return Err(DraftError::RosterFull);
+    }
+    c.check_player(config, &p, floor).map_err(DraftError::RuleViolation)?;
+    let max_bid = c.get_max_bid(config, Some(&p));
+    if amount > max_bid {
+        return Err(DraftError::InsufficientFunds { max_bid });
+    }
+    draft_state.current_bid = amount;
+    draft_state.current_winner = Some(captain.clone());
+    draft_state.bid_placed = true;
+    draft_state.bids += 1;
+    c.bid_history.push(BidRecord {
+        round: draft_state.current_round,
+        player: p.name.clone(),
+        amount,
+    });
+    info!(captain = %c.name, player = %p.name, amount, round = draft_state.current_round, "bid placed");
+    Ok(p.name.clone())
+}
+
+/// Takes a player back off their team and refunds what was paid, returns
+/// the captain's name and the refund
+pub async fn release(session: &Session, name: &str) -> Result<(String, u32), DraftError> {
+    release_where(session, name, false).await
+}
+
+/// Like `release`, but only for players kept with `/keeper`. A playing
+/// captain's own slot or a bought player stays put
+pub async fn release_keeper(session: &Session, name: &str) -> Result<(String, u32), DraftError> {
+    release_where(session, name, true).await
+}
+
+async fn release_where(session: &Session, name: &str, keepers_only: bool) -> Result<(String, u32), DraftError> {
+    for p_lock in session.players.lock().await.iter() {
+        let team = {
+            let p = p_lock.lock().await;
+            if !p.name.eq_ignore_ascii_case(name) {
+                continue;
+            }
+            p.team.clone()
+        };
+        let Some(team) = team else {
+            break;
+        };
+        // captains lock before their players, so let go and take both again
+        let mut c1 = team.lock().await;
+        let mut p = p_lock.lock().await;
+        if !p.team.as_ref().is_some_and(|t| Arc::ptr_eq(t, &team)) {
+            break;
+        }
+        let Some(purchase) = p.purchase.filter(|purchase| purchase.keeper || !keepers_only) else {
+            break;
+        };
+        p.purchase = None;
+        p.team = None;
+        p.picked = false;
+        c1.uncount_player(&p);
+        drop(p);
+        c1.players.retain(|kept| !Arc::ptr_eq(kept, p_lock));
+        // the WN8 taken off may not be what was added, if stats were refreshed
+        c1.recount().await;
+        c1.balance += purchase.price;
+        info!(captain = %c1.name, player = name, refund = purchase.price, "player released");
+        return Ok((c1.name.clone(), purchase.price));
+    }
+    Err(DraftError::NotOnTeam(name.to_string()))
+}
+
+#[derive(Clone)]
+pub struct Sale {
+    pub captain: Arc<Mutex<Captain>>,
+    pub player: Arc<Mutex<Player>>,
+    pub price: u32,
+    pub round: u32,
+    /// Bids after the nomination, 0 when the nominator got the player uncontested
+    pub bids: u32,
+}
+
+/// Gives the nominated player to the highest bidder at the current bid and
+/// closes the lot. None when nothing was nominated, the player is already on
+/// a team or the winner can no longer pay
+pub async fn sell(draft_state: &mut DraftState) -> Option<Sale> {
+    let captain = draft_state.current_winner.take()?;
+    let player = draft_state.nominated_player.take()?;
+    let name = {
+        let p1 = player.lock().await;
+        if p1.picked {
+            return None;
+        }
+        p1.name.clone()
+    };
+    let mut c1 = captain.lock().await;
+    let Some(balance) = c1.balance.checked_sub(draft_state.current_bid) else {
+        warn!(captain = %c1.name, player = name, amount = draft_state.current_bid, balance = c1.balance, "winner cannot pay, sale dropped");
+        return None;
+    };
+    c1.balance = balance;
+    info!(captain = %c1.name, player = name, amount = draft_state.current_bid, round = draft_state.current_round, bids = draft_state.bids, "player sold");
+    drop(c1);
+    add_player_to_captain(captain.clone(), player.clone(), draft_state.current_bid, draft_state.current_round).await;
+    Some(Sale {
+        captain,
+        player,
+        price: draft_state.current_bid,
+        round: draft_state.current_round,
+        bids: draft_state.bids,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraints::Constraint;
+
+    async fn session(config: &Config) -> (Session, Arc<Mutex<Captain>>, Arc<Mutex<Captain>>) {
+        let players = ["x", "y", "z"].iter()
+            .map(|n| Arc::new(Mutex::new(Player::new(n.to_string(), Vec::new()))))
+            .collect();
+        let session = Session::new(players);
+        let a = Arc::new(Mutex::new(Captain::new(1, String::from("a"), 100)));
+        let b = Arc::new(Mutex::new(Captain::new(2, String::from("b"), 100)));
+        session.captains.lock().await.extend([a.clone(), b.clone()]);
+        let mut draft_state = session.draft_state.lock().await;
+        draft_state.current_round = 1;
+        draft_state.round_captain = Some(a.clone());
+        draft_state.time = config.round_time;
+        drop(draft_state);
+        (session, a, b)
+    }
+
+    #[tokio::test]
+    async fn nominate_bid_and_sell() {
+        let config = Config { team_size: 2, ..Config::default() };
+        let (session, a, b) = session(&config).await;
+        let x = session.find_available("x").await.unwrap();
+        assert_eq!(nominate(&session, &config, &b, &x, None).await, Err(DraftError::NotYourTurn));
+        assert_eq!(nominate(&session, &config, &a, &x, None).await, Ok(config.min_bid));
+        assert_eq!(place_bid(&session, &config, &b, config.min_bid).await, Err(DraftError::UnderTopBid));
+        assert_eq!(place_bid(&session, &config, &b, 50).await, Ok(String::from("x")));
+        // a must keep min_bid for its second slot
+        assert_eq!(place_bid(&session, &config, &a, 95).await, Err(DraftError::InsufficientFunds { max_bid: 90 }));
+        assert_eq!(DraftError::InsufficientFunds { max_bid: 90 }.to_string(), "Not enough funds, Your max bid is:90");
+        let sale = sell(&mut *session.draft_state.lock().await).await.unwrap();
+        assert!(Arc::ptr_eq(&sale.captain, &b));
+        assert_eq!(b.lock().await.balance, 50);
+        assert!(x.lock().await.picked);
+        assert!(session.find_available("x").await.is_none());
+        assert!(matches!(session.nominee("X").await, Err(DraftError::PlayerUnavailable(name)) if name == "x"));
+        assert!(matches!(session.nominee("xx").await, Err(DraftError::UnknownPlayer(_))));
+        assert!(session.nominee("Y").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn captains_can_play_for_a_price() {
+        let config = Config { team_size: 3, starting_balance: 100, ..Config::default() };
+        let session = Session::new(vec![Arc::new(Mutex::new(Player::new(String::from("x"), Vec::new())))]);
+        assert_eq!(add_captain(&session, &config, 1, "a", None, None, None).await, Ok(String::from("Added captain a")));
+        assert!(add_captain(&session, &config, 1, "a2", None, None, None).await.is_err());
+        assert!(add_captain(&session, &config, 2, "b", Some("x"), None, Some(90)).await.is_err());
+        assert!(add_captain(&session, &config, 2, "b", Some("nobody"), None, None).await.is_err());
+        let added = add_captain(&session, &config, 2, "b", Some("x"), None, Some(30)).await.unwrap();
+        assert!(added.ends_with("$30"), "{}", added);
+        let b = session.find_captain(2).await.unwrap();
+        let b1 = b.lock().await;
+        assert_eq!((b1.balance, b1.players.len()), (70, 1));
+        drop(b1);
+        assert!(session.find_available("x").await.is_none());
+        add_captain(&session, &config, 3, "c", None, Some(2000), Some(10)).await.unwrap();
+        assert_eq!(session.players.lock().await.len(), 2);
+        assert_eq!(
+            add_captain(&session, &config, 4, "d", None, None, Some(u32::MAX)).await,
+            Err(DraftError::CaptainTooExpensive(u32::MAX)),
+        );
+        let steep = Config { captain_cost: crate::CaptainCost::Linear { base: 0, rate: u32::MAX }, ..config.clone() };
+        assert_eq!(
+            add_captain(&session, &steep, 4, "d", None, Some(5000), None).await,
+            Err(DraftError::CaptainTooExpensive(u32::MAX)),
+        );
+        assert_eq!(
+            add_captain(&session, &config, 4, "X", None, Some(2000), None).await,
+            Err(DraftError::CaptainNameTaken(String::from("X"))),
+        );
+    }
+
+    #[tokio::test]
+    async fn wn8_cap_keeps_room_for_the_rest_of_the_roster() {
+        let config = Config {
+            team_size: 2,
+            constraints: vec![Constraint::MaxTotalWn8 { cap: 3000 }],
+            ..Config::default()
+        };
+        let (session, a, _) = session(&config).await;
+        for (name, wn8) in [("x", 2600), ("y", 1000), ("z", 500)] {
+            session.find_available(name).await.unwrap().lock().await.recent_wn8 = wn8;
+        }
+        let players = session.players.lock().await.clone();
+        let mut eligible = Vec::new();
+        for (player, _) in eligible_players(&players, &config, &a).await {
+            eligible.push(player.lock().await.name.clone());
+        }
+        assert_eq!(eligible, vec![String::from("y"), String::from("z")]);
+        let x = session.find_available("x").await.unwrap();
+        assert!(matches!(nominate(&session, &config, &a, &x, None).await, Err(DraftError::RuleViolation(_))));
+    }
+
+    #[tokio::test]
+    async fn huge_tier_minimums_leave_nothing_to_bid() {
+        let config = Config {
+            team_size: 3,
+            tiers: vec![crate::Tier { name: String::from("s"), min_price: 3_000_000_000, min_count: 2, max_count: 3 }],
+            ..Config::default()
+        };
+        let (session, a, _) = session(&config).await;
+        assert_eq!(config.reserve(&std::collections::HashMap::new(), 2), None);
+        assert_eq!(a.lock().await.get_max_bid(&config, None), 0);
+        let players = session.players.lock().await.clone();
+        assert!(eligible_players(&players, &config, &a).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_keepers_are_released_as_keepers() {
+        let config = Config::default();
+        let (session, a, _) = session(&config).await;
+        let (x, y) = (session.find_available("x").await.unwrap(), session.find_available("y").await.unwrap());
+        crate::add_keeper(a.clone(), x, 15).await;
+        add_player_to_captain(a.clone(), y, 20, 1).await;
+        add_captain(&session, &config, 3, "c", Some("z"), None, Some(30)).await.unwrap();
+        assert_eq!(release_keeper(&session, "y").await, Err(DraftError::NotOnTeam(String::from("y"))));
+        assert_eq!(release_keeper(&session, "z").await, Err(DraftError::NotOnTeam(String::from("z"))));
+        assert_eq!(release_keeper(&session, "x").await, Ok((String::from("a"), 15)));
+        assert!(session.find_available("x").await.is_some());
+        assert!(session.find_available("z").await.is_none());
+        assert_eq!(release(&session, "y").await, Ok((String::from("a"), 20)));
+    }
+
+    #[tokio::test]
+    async fn mock_copy_leaves_real_pool_alone() {
+        let config = Config::default();
+        let (real, a, _) = session(&config).await;
+        let x = real.find_available("x").await.unwrap();
+        add_player_to_captain(a.clone(), x.clone(), 10, 0).await;
+        let mock = Session::mock_copy(&real, &[2], None, 7).await;
+        assert_eq!(mock.bots.lock().await.len(), 1);
+        let mock_a = mock.find_captain(1).await.unwrap();
+        assert_eq!(mock_a.lock().await.players.len(), 1);
+        let y = mock.find_available("y").await.unwrap();
+        add_player_to_captain(mock_a, y, 10, 1).await;
+        assert!(real.find_available("y").await.is_some());
+        assert_eq!(a.lock().await.players.len(), 1);
+    }
+
+    mod properties {
+        use super::*;
+        use crate::constraints::RosterCounts;
+        use crate::Tier;
+        use proptest::prelude::{any, prop, prop_assert, prop_assert_eq, prop_oneof, proptest, ProptestConfig};
+        use proptest::strategy::{Just, Strategy as _};
+        use proptest::test_runner::TestCaseError;
+
+        #[derive(Clone, Debug)]
+        enum Op {
+            /// The round captain puts up a player
+            Nominate { player: usize, bid: Option<u32> },
+            Bid { captain: usize, amount: u32 },
+            /// The clock runs out on the current lot
+            Sell,
+            /// A sold player goes back into the pool
+            Release { player: usize },
+        }
+
+        const CAPTAINS: usize = 3;
+        const PLAYERS: usize = 12;
+
+        fn op() -> impl proptest::strategy::Strategy<Value = Op> {
+            prop_oneof![
+                3 => (0..PLAYERS, prop::option::of(0..80u32)).prop_map(|(player, bid)| Op::Nominate { player, bid }),
+                4 => (0..CAPTAINS, 0..80u32).prop_map(|(captain, amount)| Op::Bid { captain, amount }),
+                2 => Just(Op::Sell),
+                1 => (0..PLAYERS).prop_map(|player| Op::Release { player }),
+            ]
+        }
+
+        fn config() -> Config {
+            Config {
+                team_size: 3,
+                starting_balance: 60,
+                tiers: vec![Tier { name: String::from("a"), min_price: 15, min_count: 0, max_count: 1 }],
+                ..Config::default()
+            }
+        }
+
+        async fn setup(legio: &[bool], tier_a: &[bool]) -> Session {
+            let players = (0..PLAYERS).map(|i| {
+                let tags = if legio[i] { vec![String::from("legio")] } else { Vec::new() };
+                let mut p = Player::new(format!("p{}", i), tags);
+                p.tier = tier_a[i].then(|| String::from("a"));
+                Arc::new(Mutex::new(p))
+            }).collect();
+            let session = Session::new(players);
+            for id in 0..CAPTAINS as u64 {
+                add_captain(&session, &config(), id, &format!("c{}", id), None, None, None).await.unwrap();
+            }
+            session
+        }
+
+        /// Gives the next captain with an open slot the nomination, false when everyone is full
+        async fn next_round(session: &Session, config: &Config, turn: &mut usize) -> bool {
+            let captains = session.captains.lock().await.clone();
+            for _ in 0..CAPTAINS {
+                let captain = captains[*turn % CAPTAINS].clone();
+                *turn += 1;
+                if (captain.lock().await.players.len() as u32) < config.team_size {
+                    session.draft_state.lock().await.start_round(*turn as u32, captain, config);
+                    return true;
+                }
+            }
+            false
+        }
+
+        async fn check_invariants(session: &Session, config: &Config) -> Result<(), TestCaseError> {
+            let captains = session.captains.lock().await.clone();
+            let mut rostered = Vec::new();
+            for captain in captains.iter() {
+                let c1 = captain.lock().await;
+                let mut spent = 0;
+                let mut counts = RosterCounts::default();
+                for player in c1.players.iter() {
+                    let p1 = player.lock().await;
+                    prop_assert!(p1.picked, "{} is on {} but not picked", p1.name, c1.name);
+                    prop_assert!(p1.team.as_ref().is_some_and(|t| Arc::ptr_eq(t, captain)));
+                    prop_assert!(!rostered.iter().any(|r| Arc::ptr_eq(r, player)), "{} is on two teams", p1.name);
+                    spent += p1.purchase.map(|p| p.price).unwrap_or(0);
+                    counts.add(&p1);
+                    rostered.push(player.clone());
+                }
+                let open_slots = config.team_size.saturating_sub(c1.players.len() as u32);
+                prop_assert!(open_slots <= config.team_size && c1.players.len() as u32 <= config.team_size);
+                prop_assert_eq!(c1.balance + spent, config.starting_balance, "money leaked for {}", c1.name);
+                prop_assert!(c1.balance >= open_slots * config.min_bid,
+                    "{} has ${} for {} open slots", c1.name, c1.balance, open_slots);
+                prop_assert_eq!(c1.counts.tag("legio"), counts.tag("legio"));
+                prop_assert_eq!(&c1.counts, &counts);
+                prop_assert!(c1.counts.tag("legio") <= 2);
+                prop_assert!(c1.counts.tier("a") <= 1);
+            }
+            for player in session.players.lock().await.iter() {
+                let p1 = player.lock().await;
+                let on_roster = rostered.iter().any(|r| Arc::ptr_eq(r, player));
+                prop_assert_eq!(p1.picked, on_roster, "{} picked flag disagrees with rosters", p1.name);
+                prop_assert_eq!(p1.team.is_some(), on_roster);
+            }
+            Ok(())
+        }
+
+        async fn run(ops: Vec<Op>, legio: Vec<bool>, tier_a: Vec<bool>) -> Result<(), TestCaseError> {
+            let config = config();
+            let session = setup(&legio, &tier_a).await;
+            let mut turn = 0;
+            next_round(&session, &config, &mut turn).await;
+            for op in ops {
+                match op {
+                    Op::Nominate { player, bid } => {
+                        let Some(captain) = session.draft_state.lock().await.round_captain.clone() else {
+                            continue;
+                        };
+                        let player = session.players.lock().await[player].clone();
+                        let _ = nominate(&session, &config, &captain, &player, bid).await;
+                    }
+                    Op::Bid { captain, amount } => {
+                        let captain = session.captains.lock().await[captain].clone();
+                        let _ = place_bid(&session, &config, &captain, amount).await;
+                    }
+                    Op::Sell => {
+                        let mut draft_state = session.draft_state.lock().await;
+                        if draft_state.nominated_player.is_none() {
+                            continue;
+                        }
+                        sell(&mut draft_state).await;
+                        drop(draft_state);
+                        if !next_round(&session, &config, &mut turn).await {
+                            session.draft_state.lock().await.round_captain = None;
+                        }
+                    }
+                    Op::Release { player } => {
+                        let name = format!("p{}", player);
+                        let finished = session.draft_state.lock().await.round_captain.is_none();
+                        if release(&session, &name).await.is_ok() && finished {
+                            next_round(&session, &config, &mut turn).await;
+                        }
+                    }
+                }
+                check_invariants(&session, &config).await?;
+            }
+            Ok(())
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(200))]
+
+            #[test]
+            fn money_and_rosters_stay_consistent(
+                ops in prop::collection::vec(op(), 1..80),
+                legio in prop::collection::vec(any::<bool>(), PLAYERS),
+                tier_a in prop::collection::vec(any::<bool>(), PLAYERS),
+            ) {
+                let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+                runtime.block_on(run(ops, legio, tier_a))?;
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use poise::ChoiceParameter;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::Mutex;
+
+use auction2025::auction::{self, Sale, Session};
+use auction2025::bots::{Bot, Strategy};
+use auction2025::runner::{self, DraftOutput, IntervalClock};
+use auction2025::{
+    draft_board, final_draft_board, load_players, Board, Config, Error, PLAYERS_PATH,
+};
+
+const USAGE: &str = "usage: local [--players <csv>] [--<setting> <value>]...
+settings are the ones `config` takes, e.g. --team-size 4 --round-time 20";
+
+const HELP: &str = "commands:
+  captain <name>                    add a captain you play yourself
+  bot <name> [value|pacing|random]  add a bot captain
+  start                             run the auction
+  nominate <captain> <player> [bid] put a player up on the round captain's turn
+  bid <captain> <amount>            raise the bid for a captain
+  pause, resume                     hold or restart the clock
+  status                            print the draft board
+  teams                             print every roster
+  pool                              list players still available
+  config [<setting> <value>]        print or change the config
+  quit";
+
+/// Prints the draft to stdout. The board is printed again only when more
+/// than the clock changed, the clock itself near the end of a lot
+#[derive(Default)]
+struct Terminal {
+    last: Option<Board>,
+}
+
+/// The board with the "Time Left" line taken out, and that line
+fn split_clock(board: &Board) -> (Board, Option<String>) {
+    let mut clock = None;
+    let mut rest = board.clone();
+    for (_, value, _) in rest.fields.iter_mut() {
+        let mut lines = Vec::new();
+        for line in value.lines() {
+            if line.starts_with("Time Left") {
+                clock = Some(line.to_string());
+            } else {
+                lines.push(line);
+            }
+        }
+        *value = lines.join("\n");
+    }
+    (rest, clock)
+}
+
+impl DraftOutput for Terminal {
+    async fn board(&mut self, board: Board) -> Result<(), Error> {
+        let (rest, clock) = split_clock(&board);
+        let (last, last_clock) = self.last.as_ref().map(split_clock).unzip();
+        if last.as_ref() != Some(&rest) {
+            print!("{}", board);
+        } else if let Some(clock) = clock.filter(|c| last_clock.flatten().as_ref() != Some(c)) {
+            let seconds: u32 = clock.split_whitespace().nth(2).and_then(|s| s.parse().ok()).unwrap_or(0);
+            if seconds <= 6 || seconds.is_multiple_of(10) {
+                println!("  {}", clock);
+            }
+        }
+        self.last = Some(board);
+        Ok(())
+    }
+
+    async fn status(&mut self, text: String) -> Result<(), Error> {
+        println!("> {}", text);
+        Ok(())
+    }
+
+    async fn sold(&mut self, sale: &Sale) -> Result<(), Error> {
+        println!("> {} bought {} for ${}{}",
+            sale.captain.lock().await.name,
+            sale.player.lock().await.name,
+            sale.price,
+            if sale.bids == 0 { ", no other bids" } else { "" },
+        );
+        Ok(())
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<(String, Config), String> {
+    let mut players = String::from(PLAYERS_PATH);
+    let mut config = Config::default();
+    let mut args = args.peekable();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--players" => players = value,
+            _ => {
+                let key = flag.strip_prefix("--").ok_or_else(|| format!("Unknown option {}", flag))?;
+                config.set(&key.replace('-', "_"), &value).map_err(|e| format!("{}: {}", flag, e))?;
+            }
+        }
+    }
+    Ok((players, config))
+}
+
+async fn add_captain(session: &Session, config: &Mutex<Config>, name: &str, strategy: Option<Strategy>) -> Result<String, String> {
+    if name.is_empty() {
+        return Err(String::from("Give the captain a name"));
+    }
+    if session.find_captain_by_name(name).await.is_some() {
+        return Err(format!("{} is already a captain", name));
+    }
+    if session.draft_state.lock().await.draft_started {
+        return Err(String::from("The draft has started"));
+    }
+    let id = session.captains.lock().await.len() as u64 + 1;
+    let config = config.lock().await.clone();
+    let added = auction::add_captain(session, &config, id, name, None, None, None).await.map_err(|e| e.to_string())?;
+    let Some(strategy) = strategy else {
+        return Ok(added);
+    };
+    let captain = session.find_captain(id).await.ok_or("Captain went missing")?;
+    session.bots.lock().await.push(Bot::new(captain, strategy, rand::random()));
+    Ok(format!("{} ({} bot)", added, strategy.name()))
+}
+
+async fn start(session: &Arc<Session>, config: &Arc<Mutex<Config>>) -> Result<String, String> {
+    if session.draft_state.lock().await.draft_started {
+        return Err(String::from("The draft has started"));
+    }
+    let captains = session.captains.lock().await.clone();
+    if captains.is_empty() {
+        return Err(String::from("Add captains first"));
+    }
+    let starting_balance = config.lock().await.starting_balance;
+    for captain in captains.iter() {
+        captain.lock().await.balance = starting_balance;
+    }
+    let (session, config) = (session.clone(), config.clone());
+    tokio::spawn(async move {
+        runner::run_auction(&session, &config, &mut Terminal::default(), &mut IntervalClock::default()).await;
+        session.draft_state.lock().await.draft_finished = true;
+        println!("Draft finished, `teams` shows the rosters");
+    });
+    Ok(String::from("Starting"))
+}
+
+async fn nominate(session: &Session, config: &Mutex<Config>, args: &[&str]) -> Result<String, String> {
+    let [captain, rest @ ..] = args else {
+        return Err(String::from("usage: nominate <captain> <player> [bid]"));
+    };
+    let (name, starting_bid) = match rest.split_last() {
+        Some((last, name)) if !name.is_empty() && last.parse::<u32>().is_ok() => (name.join(" "), last.parse().ok()),
+        _ => (rest.join(" "), None),
+    };
+    let captain = session.find_captain_by_name(captain).await
+        .ok_or_else(|| format!("No captain named {}", captain))?;
+    let player = session.nominee(&name).await.map_err(|e| e.to_string())?;
+    let config = config.lock().await.clone();
+    let bid = auction::nominate(session, &config, &captain, &player, starting_bid).await.map_err(|e| e.to_string())?;
+    Ok(format!("Nominated {} at ${}", player.lock().await.name, bid))
+}
+
+async fn bid(session: &Session, config: &Mutex<Config>, args: &[&str]) -> Result<String, String> {
+    let [captain, amount] = args else {
+        return Err(String::from("usage: bid <captain> <amount>"));
+    };
+    let amount = amount.parse().map_err(|_| format!("{} is not a number", amount))?;
+    let captain = session.find_captain_by_name(captain).await
+        .ok_or_else(|| format!("No captain named {}", captain))?;
+    let config = config.lock().await.clone();
+    let player = auction::place_bid(session, &config, &captain, amount).await.map_err(|e| e.to_string())?;
+    Ok(format!("Bid ${} for {}", amount, player))
+}
+
+async fn pool(session: &Session) -> String {
+    let mut available = Vec::new();
+    for player in session.players.lock().await.iter() {
+        let p1 = player.lock().await;
+        if !p1.picked {
+            available.push((p1.recent_wn8, format!("{} [{}] {}",
+                p1.team_line(),
+                p1.tier.as_deref().unwrap_or("-"),
+                p1.tags.join(", "),
+            )));
+        }
+    }
+    available.sort_by_key(|p| std::cmp::Reverse(p.0));
+    let lines: Vec<String> = available.into_iter().map(|(_, line)| line).collect();
+    if lines.is_empty() {
+        String::from("No players left")
+    } else {
+        lines.join("\n")
+    }
+}
+
+async fn run_command(session: &Arc<Session>, config: &Arc<Mutex<Config>>, line: &str) -> Result<String, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let Some((command, args)) = words.split_first() else {
+        return Ok(String::new());
+    };
+    match *command {
+        "help" => Ok(String::from(HELP)),
+        "captain" => add_captain(session, config, &args.join(" "), None).await,
+        "bot" => {
+            let (name, strategy) = match args {
+                [name, strategy] => {
+                    let strategy = Strategy::ALL.into_iter()
+                        .find(|s| s.name().eq_ignore_ascii_case(strategy))
+                        .ok_or_else(|| format!("Unknown strategy {}", strategy))?;
+                    (*name, strategy)
+                }
+                [name] => (*name, Strategy::ALL[session.bots.lock().await.len() % Strategy::ALL.len()]),
+                _ => return Err(String::from("usage: bot <name> [value|pacing|random]")),
+            };
+            add_captain(session, config, name, Some(strategy)).await
+        }
+        "start" => start(session, config).await,
+        "nominate" => nominate(session, config, args).await,
+        "bid" => bid(session, config, args).await,
+        "pause" | "resume" => {
+            let mut draft_state = session.draft_state.lock().await;
+            if !draft_state.draft_started || draft_state.draft_finished {
+                return Err(String::from("No Draft Running"));
+            }
+            draft_state.paused = *command == "pause";
+            Ok(String::from(if draft_state.paused { "Paused" } else { "Resumed" }))
+        }
+        "status" => {
+            let captains = session.captains.lock().await.clone();
+            let draft_state = session.draft_state.lock().await;
+            Ok(draft_board(&draft_state, &captains, &*config.lock().await).await.to_string())
+        }
+        "teams" => Ok(final_draft_board(session.captains.lock().await.clone()).await.to_string()),
+        "pool" => Ok(pool(session).await),
+        "config" => match args {
+            [] => Ok(config.lock().await.to_string()),
+            [key, value @ ..] if !value.is_empty() => {
+                config.lock().await.set(key, &value.join(" "))?;
+                Ok(format!("Set {}", key))
+            }
+            _ => Err(String::from("usage: config <setting> <value>")),
+        },
+        _ => Err(format!("Unknown command {}, try help", command)),
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let (players_path, config) = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let players = match load_players(&players_path) {
+        Ok(players) => players,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", players_path, e);
+            std::process::exit(1);
+        }
+    };
+    println!("{} players loaded, type help for commands", players.len());
+    let session = Arc::new(Session::new(players));
+    let config = Arc::new(Mutex::new(config));
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim() == "quit" {
+            break;
+        }
+        match run_command(&session, &config, &line).await {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => println!("{}", text),
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use poise::ChoiceParameter;
+use std::sync::Arc;
+
+use auction2025::bots::Strategy;
+use auction2025::{load_players, simulate, Config, PLAYERS_PATH};
+
+const USAGE: &str = "usage: simulate [--players <csv>] [--runs <n>] [--captains <n>] [--team-size <n>]
+                [--starting-balance <n>] [--min-bid <n>] [--round-time <n>] [--bid-add-time <n>]
+                [--constraint <rule>]
+                [--strategy value|pacing|random] [--seed <n>]";
+
+struct Options {
+    players: String,
+    runs: u64,
+    captains: u32,
+    strategy: Option<Strategy>,
+    seed: u64,
+    config: Config,
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        players: String::from(PLAYERS_PATH),
+        runs: 1000,
+        captains: 8,
+        strategy: None,
+        seed: 0,
+        config: Config::default(),
+    };
+    let mut args = args.peekable();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        let number = || value.parse::<u32>().map_err(|_| format!("{} needs a number, got {}", flag, value));
+        match flag.as_str() {
+            "--players" => options.players = value.clone(),
+            "--runs" => options.runs = number()? as u64,
+            "--captains" => options.captains = number()?,
+            "--seed" => options.seed = number()? as u64,
+            "--strategy" => {
+                options.strategy = Some(Strategy::ALL.into_iter()
+                    .find(|s| s.name().eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("Unknown strategy {}", value))?);
+            }
+            _ => {
+                let key = flag.strip_prefix("--").ok_or_else(|| format!("Unknown option {}", flag))?;
+                options.config.set(&key.replace('-', "_"), &value)
+                    .map_err(|e| format!("{}: {}", flag, e))?;
+            }
+        }
+    }
+    if options.captains == 0 || options.config.team_size == 0 {
+        return Err(String::from("Need at least one captain and a team size above 0"));
+    }
+    Ok(options)
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let players = match load_players(&options.players) {
+        Ok(players) => players,
+        Err(e) => {
+            eprintln!("Could not read {}: {}", options.players, e);
+            std::process::exit(1);
+        }
+    };
+    let needed = options.captains * options.config.team_size;
+    if (players.len() as u32) < needed {
+        eprintln!("Warning: {} players for {} roster slots", players.len(), needed);
+    }
+    let config = Arc::new(options.config);
+    let base = Arc::new(simulate::base_session(players, options.captains, &config).await);
+    let mut handles = Vec::new();
+    for run in 0..options.runs {
+        let (base, config) = (base.clone(), config.clone());
+        let seed = options.seed.wrapping_add(run);
+        handles.push(tokio::spawn(async move {
+            simulate::run_draft(&base, &config, options.strategy, seed).await
+        }));
+    }
+    let mut runs = Vec::new();
+    for handle in handles {
+        runs.push(handle.await.expect("simulation panicked"));
+    }
+    print!("{}", simulate::summary(&runs, &config));
+}
@@ -0,0 +1,187 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::auction::{self, Session};
+use crate::{Captain, Config, Player};
+
+/// How a bot captain values players
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Strategy {
+    /// Pays more for WN8 above the pool average
+    Value,
+    /// Never pays more than an even split of the remaining budget
+    Pacing,
+    /// Random price for every player
+    Random,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [Strategy::Value, Strategy::Pacing, Strategy::Random];
+}
+
+pub struct Bot {
+    pub captain: Arc<Mutex<Captain>>,
+    pub strategy: Strategy,
+    rng: StdRng,
+}
+
+impl Bot {
+    pub fn new(captain: Arc<Mutex<Captain>>, strategy: Strategy, seed: u64) -> Self {
+        Self {
+            captain,
+            strategy,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Most this bot will pay for `player`, `average_wn8` is the average of
+    /// the players still in the pool
+    pub fn limit(&mut self, captain: &Captain, player: &Player, average_wn8: f64, config: &Config) -> u32 {
+        let slots_left = config.team_size.saturating_sub(captain.players.len() as u32).max(1);
+        let share = (captain.balance / slots_left) as f64;
+        let limit = match self.strategy {
+            Strategy::Value if average_wn8 > 0.0 => share * player.recent_wn8 as f64 / average_wn8,
+            Strategy::Value | Strategy::Pacing => share,
+            Strategy::Random => share * self.rng.gen_range(0.5..2.0),
+        };
+        (limit as u32).min(captain.get_max_bid(config, Some(player)))
+    }
+
+    /// Next bid on the nominated player, None to pass
+    pub fn bid(&mut self, captain: &Captain, player: &Player, current_bid: u32, average_wn8: f64, config: &Config) -> Option<u32> {
+        let next = current_bid + (current_bid / 10).max(1);
+        (next <= self.limit(captain, player, average_wn8, config)).then_some(next)
+    }
+
+    /// Player to put up when it is this bot's turn, at the tier's minimum price
+    pub async fn nomination(&mut self, players: &[Arc<Mutex<Player>>], config: &Config) -> Option<(Arc<Mutex<Player>>, u32)> {
+        let mut candidates = Vec::new();
+        for (player, min_price) in auction::eligible_players(players, config, &self.captain).await {
+            let wn8 = player.lock().await.recent_wn8;
+            candidates.push((player, wn8, min_price));
+        }
+        let (player, _, min_price) = match self.strategy {
+            Strategy::Random => candidates.choose(&mut self.rng).cloned()?,
+            Strategy::Value | Strategy::Pacing => candidates.into_iter().max_by_key(|c| c.1)?,
+        };
+        Some((player, min_price))
+    }
+}
+
+/// Average recent WN8 of the unpicked players plus the one being auctioned
+pub async fn pool_average(players: &[Arc<Mutex<Player>>], nominated: Option<&Arc<Mutex<Player>>>) -> f64 {
+    let mut wn8s = Vec::new();
+    for player in players {
+        let p1 = player.lock().await;
+        if !p1.picked || nominated.is_some_and(|n| Arc::ptr_eq(n, player)) {
+            wn8s.push(p1.recent_wn8);
+        }
+    }
+    crate::report::average(&wn8s)
+}
+
+/// Lets a bot nominate when it is its turn, otherwise lets the first bot
+/// that wants to raise the bid do so. Returns whether a bot acted
+pub async fn run_bots(session: &Session, config: &Config) -> bool {
+    let draft_state = session.draft_state.lock().await;
+    let round_captain = draft_state.round_captain.clone();
+    let nominated = draft_state.nominated_player.clone();
+    let winner = draft_state.current_winner.clone();
+    let current_bid = draft_state.current_bid;
+    drop(draft_state);
+    let Some(round_captain) = round_captain else {
+        return false;
+    };
+    let players = session.players.lock().await.clone();
+    let mut bots = session.bots.lock().await;
+    match nominated {
+        None => {
+            let Some(bot) = bots.iter_mut().find(|b| Arc::ptr_eq(&b.captain, &round_captain)) else {
+                return false;
+            };
+            let Some((player, starting_bid)) = bot.nomination(&players, config).await else {
+                return false;
+            };
+            auction::nominate(session, config, &bot.captain, &player, Some(starting_bid)).await.is_ok()
+        }
+        Some(player) => {
+            let average = pool_average(&players, Some(&player)).await;
+            for bot in bots.iter_mut() {
+                if winner.as_ref().is_some_and(|w| Arc::ptr_eq(w, &bot.captain)) {
+                    continue;
+                }
+                let captain = bot.captain.clone();
+                let c1 = captain.lock().await;
+                let p1 = player.lock().await;
+                let amount = bot.bid(&c1, &p1, current_bid, average, config);
+                drop(p1);
+                drop(c1);
+                if let Some(amount) = amount {
+                    if auction::place_bid(session, config, &captain, amount).await.is_ok() {
+                        return true;
+                    }
+                }
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(wn8: u32) -> Player {
+        let mut p = Player::new(String::from("p"), Vec::new());
+        p.recent_wn8 = wn8;
+        p
+    }
+
+    #[test]
+    fn strategies_price_players() {
+        let config = Config { team_size: 4, ..Config::default() };
+        let captain = Captain::new(1, String::from("c"), 200);
+        let dummy = Arc::new(Mutex::new(Captain::new(1, String::from("c"), 200)));
+        let mut value = Bot::new(dummy.clone(), Strategy::Value, 1);
+        let mut pacing = Bot::new(dummy.clone(), Strategy::Pacing, 1);
+        assert_eq!(value.limit(&captain, &player(3000), 2000.0, &config), 75);
+        assert_eq!(value.limit(&captain, &player(1000), 2000.0, &config), 25);
+        assert_eq!(pacing.limit(&captain, &player(3000), 2000.0, &config), 50);
+        assert_eq!(pacing.bid(&captain, &player(3000), 40, 2000.0, &config), Some(44));
+        assert_eq!(pacing.bid(&captain, &player(3000), 50, 2000.0, &config), None);
+        let mut random = Bot::new(dummy, Strategy::Random, 1);
+        for _ in 0..20 {
+            let limit = random.limit(&captain, &player(3000), 2000.0, &config);
+            assert!((25..=100).contains(&limit), "{}", limit);
+        }
+    }
+
+    #[tokio::test]
+    async fn bots_finish_an_auction_round() {
+        let config = Config { team_size: 2, ..Config::default() };
+        let players = [2500, 1500, 1000].iter()
+            .map(|w| Arc::new(Mutex::new(player(*w))))
+            .collect();
+        let real = Session::new(players);
+        for id in 1..=2 {
+            real.captains.lock().await.push(Arc::new(Mutex::new(Captain::new(id, id.to_string(), 100))));
+        }
+        let session = Session::mock_copy(&real, &[], Some(Strategy::Value), 3).await;
+        let nominator = session.captains.lock().await[0].clone();
+        let mut draft_state = session.draft_state.lock().await;
+        draft_state.round_captain = Some(nominator.clone());
+        draft_state.time = config.round_time;
+        drop(draft_state);
+        assert!(run_bots(&session, &config).await);
+        let nominated = session.draft_state.lock().await.nominated_player.clone().unwrap();
+        assert_eq!(nominated.lock().await.recent_wn8, 2500);
+        while run_bots(&session, &config).await {}
+        let mut draft_state = session.draft_state.lock().await;
+        assert!(draft_state.current_bid > config.min_bid);
+        let sale = auction::sell(&mut draft_state).await.unwrap();
+        assert!(sale.captain.lock().await.balance >= config.min_bid);
+    }
+}
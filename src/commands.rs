@@ -0,0 +1,215 @@
+use tokio::sync::Mutex;
+
+use crate::auction::{self, Session};
+use crate::error::DraftError;
+use crate::runner::CommandOutput;
+use crate::{CaptainCost, Config, Error};
+
+/// Settings `/config` can change, `None` leaves one as it is
+#[derive(Clone, Debug, Default)]
+pub struct ConfigChanges {
+    pub round_time: Option<u32>,
+    pub bid_add_time: Option<u32>,
+    pub min_bid: Option<u32>,
+    pub starting_balance: Option<u32>,
+    pub team_size: Option<u32>,
+    pub dm_players: Option<bool>,
+    pub free_agency_time: Option<u32>,
+    pub trade_approval: Option<bool>,
+    pub captain_cost: Option<String>,
+}
+
+/// Applies `changes` and replies with the whole config. Starting balance and
+/// team size are fixed once the draft starts and the team size can't drop
+/// below the tier minimums, a refused change leaves every setting alone
+pub async fn config<O: CommandOutput + Send>(
+    session: &Session,
+    config: &Mutex<Config>,
+    changes: ConfigChanges,
+    output: &mut O,
+) -> Result<(), Error> {
+    let draft_started = session.draft_state.lock().await.draft_started;
+    if draft_started && changes.starting_balance.is_some() {
+        return output.reply(String::from("Cannot change starting balance after start"), true).await;
+    }
+    if draft_started && changes.team_size.is_some() {
+        return output.reply(String::from("Cannot change team size after start"), true).await;
+    }
+    let captain_cost = match changes.captain_cost.as_deref().map(str::parse::<CaptainCost>).transpose() {
+        Ok(cost) => cost,
+        Err(e) => return output.reply(e, true).await,
+    };
+    let mut config = config.lock().await;
+    let tier_minimums: u32 = config.tiers.iter().map(|t| t.min_count).sum();
+    if let Some(ts) = changes.team_size.filter(|ts| *ts < tier_minimums) {
+        drop(config);
+        return output.reply(format!("Tier minimums add up to {}, more than a team size of {}", tier_minimums, ts), true).await;
+    }
+    if let Some(rt) = changes.round_time {
+        config.round_time = rt;
+    }
+    if let Some(bt) = changes.bid_add_time {
+        config.bid_add_time = bt;
+    }
+    if let Some(mb) = changes.min_bid {
+        config.min_bid = mb;
+    }
+    if let Some(sb) = changes.starting_balance {
+        config.starting_balance = sb;
+    }
+    if let Some(ts) = changes.team_size {
+        config.team_size = ts;
+    }
+    if let Some(dm) = changes.dm_players {
+        config.dm_players = dm;
+    }
+    if let Some(fa) = changes.free_agency_time {
+        config.free_agency_time = fa;
+    }
+    if let Some(ta) = changes.trade_approval {
+        config.trade_approval = ta;
+    }
+    if let Some(cost) = captain_cost {
+        config.captain_cost = cost;
+    }
+    let text = config.to_string();
+    drop(config);
+    output.reply(text, false).await
+}
+
+/// Adds the captain for `discord_id` and announces what they cost
+#[allow(clippy::too_many_arguments)]
+pub async fn add_captain<O: CommandOutput + Send>(
+    session: &Session,
+    config: &Config,
+    discord_id: u64,
+    name: &str,
+    player: Option<&str>,
+    wn8: Option<u32>,
+    value: Option<u32>,
+    output: &mut O,
+) -> Result<(), Error> {
+    let content = auction::add_captain(session, config, discord_id, name, player, wn8, value).await?;
+    output.reply(content, false).await
+}
+
+/// The author nominates `player` on their turn. The board announces the
+/// lot, the author only gets a private confirmation
+pub async fn pick<O: CommandOutput + Send>(
+    session: &Session,
+    config: &Config,
+    author: u64,
+    player: &str,
+    starting_bid: Option<u32>,
+    output: &mut O,
+) -> Result<(), Error> {
+    let captain = session.find_captain(author).await.ok_or(DraftError::NotYourTurn)?;
+    let target = session.nominee(player).await?;
+    let opening = auction::nominate(session, config, &captain, &target, starting_bid).await?;
+    let name = target.lock().await.name.clone();
+    output.reply(format!("You nominated {} for ${}", name, opening), true).await
+}
+
+/// The author raises the bid on the player up for auction
+pub async fn bid<O: CommandOutput + Send>(
+    session: &Session,
+    config: &Config,
+    author: u64,
+    amount: u32,
+    output: &mut O,
+) -> Result<(), Error> {
+    let captain = session.find_captain(author).await.ok_or(DraftError::NotCaptain)?;
+    let player = auction::place_bid(session, config, &captain, amount).await?;
+    output.reply(format!("You Bid ${} for {}", amount, player), true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::{Recorder, Sent};
+    use crate::Player;
+    use std::sync::Arc;
+
+    fn session() -> Session {
+        let players = ["x", "y"].iter()
+            .map(|n| Arc::new(Mutex::new(Player::new(n.to_string(), Vec::new()))))
+            .collect();
+        Session::new(players)
+    }
+
+    fn draft_error(result: Result<(), Error>) -> DraftError {
+        result.unwrap_err().downcast::<DraftError>().map(|e| *e).unwrap()
+    }
+
+    #[tokio::test]
+    async fn config_refuses_all_or_nothing() {
+        let session = session();
+        let settings = Mutex::new(Config::default());
+        let mut out = Recorder::default();
+        let changes = ConfigChanges { round_time: Some(30), captain_cost: Some(String::from("bogus")), ..ConfigChanges::default() };
+        config(&session, &settings, changes, &mut out).await.unwrap();
+        assert_eq!(settings.lock().await.round_time, Config::default().round_time);
+        assert!(matches!(&out.sent[0], Sent::Reply { private: true, .. }));
+
+        let changes = ConfigChanges { round_time: Some(30), team_size: Some(3), ..ConfigChanges::default() };
+        config(&session, &settings, changes.clone(), &mut out).await.unwrap();
+        let set = settings.lock().await.clone();
+        assert_eq!((set.round_time, set.team_size), (30, 3));
+        assert!(out.replies()[1].contains("Team Size: 3"), "{}", out.replies()[1]);
+
+        session.draft_state.lock().await.draft_started = true;
+        let changes = ConfigChanges { team_size: Some(4), ..changes };
+        config(&session, &settings, changes, &mut out).await.unwrap();
+        assert_eq!(out.replies()[2], "Cannot change team size after start");
+        assert_eq!(settings.lock().await.team_size, 3);
+    }
+
+    #[tokio::test]
+    async fn config_keeps_team_size_above_tier_minimums() {
+        let session = session();
+        let tiers = vec![
+            crate::Tier { name: String::from("s"), min_price: 20, min_count: 2, max_count: 3 },
+            crate::Tier { name: String::from("a"), min_price: 15, min_count: 1, max_count: 3 },
+        ];
+        let settings = Mutex::new(Config { tiers, ..Config::default() });
+        let mut out = Recorder::default();
+        let changes = ConfigChanges { min_bid: Some(5), team_size: Some(2), ..ConfigChanges::default() };
+        config(&session, &settings, changes, &mut out).await.unwrap();
+        assert_eq!(out.replies()[0], "Tier minimums add up to 3, more than a team size of 2");
+        let set = settings.lock().await.clone();
+        assert_eq!((set.min_bid, set.team_size), (Config::default().min_bid, Config::default().team_size));
+
+        let changes = ConfigChanges { team_size: Some(3), ..ConfigChanges::default() };
+        config(&session, &settings, changes, &mut out).await.unwrap();
+        assert_eq!(settings.lock().await.team_size, 3);
+    }
+
+    #[tokio::test]
+    async fn pick_and_bid_answer_the_author() {
+        let session = session();
+        let config = Config { team_size: 2, ..Config::default() };
+        let mut out = Recorder::default();
+        add_captain(&session, &config, 1, "a", None, None, None, &mut out).await.unwrap();
+        add_captain(&session, &config, 2, "b", None, None, None, &mut out).await.unwrap();
+        assert_eq!(out.replies(), vec!["Added captain a", "Added captain b"]);
+        assert_eq!(
+            draft_error(add_captain(&session, &config, 1, "c", None, None, None, &mut out).await),
+            DraftError::AlreadyCaptain,
+        );
+
+        let a = session.find_captain(1).await.unwrap();
+        let mut draft_state = session.draft_state.lock().await;
+        draft_state.current_round = 1;
+        draft_state.round_captain = Some(a);
+        draft_state.time = config.round_time;
+        drop(draft_state);
+        assert_eq!(draft_error(pick(&session, &config, 2, "x", None, &mut out).await), DraftError::NotYourTurn);
+        assert_eq!(draft_error(pick(&session, &config, 9, "x", None, &mut out).await), DraftError::NotYourTurn);
+        pick(&session, &config, 1, "X", Some(12), &mut out).await.unwrap();
+        assert_eq!(draft_error(bid(&session, &config, 9, 20, &mut out).await), DraftError::NotCaptain);
+        assert_eq!(draft_error(bid(&session, &config, 2, 12, &mut out).await), DraftError::UnderTopBid);
+        bid(&session, &config, 2, 20, &mut out).await.unwrap();
+        assert_eq!(out.replies()[2..], ["You nominated x for $12", "You Bid $20 for x"]);
+        assert!(matches!(out.sent.last(), Some(Sent::Reply { private: true, .. })));
+    }
+}
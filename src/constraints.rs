@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::Player;
+
+/// Running totals for a roster, kept on the captain so rules can be checked
+/// without locking every player
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RosterCounts {
+    pub size: u32,
+    pub total_wn8: u32,
+    pub tags: BTreeMap<String, u32>,
+    pub clans: HashMap<String, u32>,
+    pub tiers: HashMap<String, u32>,
+}
+
+impl RosterCounts {
+    pub fn add(&mut self, player: &Player) {
+        self.size += 1;
+        self.total_wn8 = self.total_wn8.saturating_add(player.recent_wn8);
+        for tag in player.tags.iter() {
+            *self.tags.entry(tag.clone()).or_default() += 1;
+        }
+        if let Some(clan) = &player.clan {
+            *self.clans.entry(clan.clone()).or_default() += 1;
+        }
+        if let Some(tier) = &player.tier {
+            *self.tiers.entry(tier.clone()).or_default() += 1;
+        }
+    }
+
+    /// Takes `player` back out. Saturating because their WN8 may have been
+    /// refreshed since they were added, `Captain::recount` puts the total right
+    pub fn remove(&mut self, player: &Player) {
+        self.size = self.size.saturating_sub(1);
+        self.total_wn8 = self.total_wn8.saturating_sub(player.recent_wn8);
+        for tag in player.tags.iter() {
+            decrement(self.tags.get_mut(tag));
+        }
+        if let Some(clan) = &player.clan {
+            decrement(self.clans.get_mut(clan));
+        }
+        if let Some(tier) = &player.tier {
+            decrement(self.tiers.get_mut(tier));
+        }
+        // a roster that lost a player should look the same as one that never had them
+        self.tags.retain(|_, n| *n > 0);
+        self.clans.retain(|_, n| *n > 0);
+        self.tiers.retain(|_, n| *n > 0);
+    }
+
+    pub fn tag(&self, tag: &str) -> u32 {
+        *self.tags.get(tag).unwrap_or(&0)
+    }
+
+    pub fn tier(&self, tier: &str) -> u32 {
+        *self.tiers.get(tier).unwrap_or(&0)
+    }
+}
+
+fn decrement(count: Option<&mut u32>) {
+    if let Some(count) = count {
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// A roster rule, checked whenever a captain nominates, bids or trades
+#[derive(Clone, Debug, PartialEq)]
+pub enum Constraint {
+    MaxTag { tag: String, count: u32 },
+    MinTag { tag: String, count: u32 },
+    MaxTotalWn8 { cap: u32 },
+    /// Average recent WN8 of a full team, so a roster may total `cap * team_size`
+    MaxAverageWn8 { cap: u32 },
+    MaxPerClan { count: u32 },
+}
+
+impl Constraint {
+    /// Most WN8 a full roster may add up to, `None` for rules that don't cap WN8
+    pub fn wn8_limit(&self, team_size: u32) -> Option<u64> {
+        match self {
+            Constraint::MaxTotalWn8 { cap } => Some(*cap as u64),
+            Constraint::MaxAverageWn8 { cap } => Some(*cap as u64 * team_size as u64),
+            _ => None,
+        }
+    }
+
+    /// Checks a roster that still has `open_slots` to fill. Under a WN8 cap
+    /// every open slot takes at least `wn8_floor`, the weakest player left in
+    /// the pool, so the roster has to leave that much room per slot
+    pub fn check(&self, roster: &RosterCounts, open_slots: u32, wn8_floor: u32) -> Result<(), String> {
+        match self {
+            Constraint::MaxTag { tag, count } => {
+                if roster.tag(tag) > *count {
+                    return Err(format!("Teams can have at most {} {} players", count, tag));
+                }
+            }
+            Constraint::MinTag { tag, count } => {
+                let missing = count.saturating_sub(roster.tag(tag));
+                if missing > open_slots {
+                    return Err(format!("Teams need at least {} {} players, not enough slots left", count, tag));
+                }
+            }
+            Constraint::MaxTotalWn8 { .. } | Constraint::MaxAverageWn8 { .. } => {
+                let limit = self.wn8_limit(roster.size.saturating_add(open_slots)).unwrap_or(u64::MAX);
+                let total = roster.total_wn8 as u64;
+                if total > limit {
+                    return Err(format!("Team WN8 would be {}, the cap is {}", total, limit));
+                }
+                if total + open_slots as u64 * wn8_floor as u64 > limit {
+                    return Err(format!(
+                        "Team WN8 would be {}, leaving no room under the cap of {} for {} more players",
+                        total,
+                        limit,
+                        open_slots,
+                    ));
+                }
+            }
+            Constraint::MaxPerClan { count } => {
+                if let Some((clan, _)) = roster.clans.iter().find(|(_, n)| **n > *count) {
+                    return Err(format!("Teams can have at most {} players from {}", count, clan));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// How the roster stands against this rule, for dashboards
+    pub fn status(&self, roster: &RosterCounts, team_size: u32) -> String {
+        match self {
+            Constraint::MaxTag { tag, count } => format!("{} {}: `{}/{}`", "Max", tag, roster.tag(tag), count),
+            Constraint::MinTag { tag, count } => format!("{} {}: `{}/{}`", "Min", tag, roster.tag(tag), count),
+            Constraint::MaxTotalWn8 { cap } => format!("WN8 cap: `{}/{}`", roster.total_wn8, cap),
+            Constraint::MaxAverageWn8 { cap } => format!("WN8 cap: `{}/{}` ({} average)",
+                roster.total_wn8,
+                *cap as u64 * team_size as u64,
+                cap,
+            ),
+            Constraint::MaxPerClan { count } => format!("Per clan: `{}/{}`",
+                roster.clans.values().max().unwrap_or(&0),
+                count,
+            ),
+        }
+    }
+}
+
+/// First rule the roster breaks
+pub fn check_all(
+    constraints: &[Constraint],
+    roster: &RosterCounts,
+    open_slots: u32,
+    wn8_floor: u32,
+) -> Result<(), String> {
+    for constraint in constraints {
+        constraint.check(roster, open_slots, wn8_floor)?;
+    }
+    Ok(())
+}
+
+/// WN8 a roster can still take on under the tightest cap, `None` when no rule caps WN8
+pub fn wn8_space(constraints: &[Constraint], roster: &RosterCounts, team_size: u32) -> Option<u64> {
+    constraints
+        .iter()
+        .filter_map(|c| c.wn8_limit(team_size))
+        .min()
+        .map(|limit| limit.saturating_sub(roster.total_wn8 as u64))
+}
+
+impl std::str::FromStr for Constraint {
+    type Err = String;
+
+    /// `max_tag:<tag>:<n>`, `min_tag:<tag>:<n>`, `max_wn8:<cap>`, `max_wn8:avg:<cap>` or `max_clan:<n>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!(
+            "Invalid constraint `{}`, use max_tag:<tag>:<n>, min_tag:<tag>:<n>, max_wn8:<cap>, max_wn8:avg:<cap> or max_clan:<n>",
+            s,
+        );
+        let parts: Vec<&str> = s.split(':').map(|p| p.trim()).collect();
+        let number = |p: &str| p.parse::<u32>().map_err(|_| invalid());
+        match parts.as_slice() {
+            ["max_tag", tag, n] if !tag.is_empty() => Ok(Constraint::MaxTag { tag: tag.to_lowercase(), count: number(n)? }),
+            ["min_tag", tag, n] if !tag.is_empty() => Ok(Constraint::MinTag { tag: tag.to_lowercase(), count: number(n)? }),
+            ["max_wn8", cap] => Ok(Constraint::MaxTotalWn8 { cap: number(cap)? }),
+            ["max_wn8", "avg" | "average", cap] => Ok(Constraint::MaxAverageWn8 { cap: number(cap)? }),
+            ["max_clan", n] => Ok(Constraint::MaxPerClan { count: number(n)? }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Constraint::MaxTag { tag, count } => write!(f, "max_tag:{}:{}", tag, count),
+            Constraint::MinTag { tag, count } => write!(f, "min_tag:{}:{}", tag, count),
+            Constraint::MaxTotalWn8 { cap } => write!(f, "max_wn8:{}", cap),
+            Constraint::MaxAverageWn8 { cap } => write!(f, "max_wn8:avg:{}", cap),
+            Constraint::MaxPerClan { count } => write!(f, "max_clan:{}", count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(tags: &[&str], clan: Option<&str>, wn8: u32) -> Player {
+        let mut p = Player::new(String::from("p"), tags.iter().map(|t| t.to_string()).collect());
+        p.clan = clan.map(|c| c.to_string());
+        p.recent_wn8 = wn8;
+        p
+    }
+
+    #[test]
+    fn parses_and_prints() {
+        for rule in ["max_tag:legio:2", "min_tag:arty:1", "max_wn8:20000", "max_clan:3"] {
+            let constraint: Constraint = rule.parse().unwrap();
+            assert_eq!(constraint.to_string(), rule);
+        }
+        assert!("max_tag:legio".parse::<Constraint>().is_err());
+        assert!("max_wn8:lots".parse::<Constraint>().is_err());
+    }
+
+    #[test]
+    fn counts_follow_players() {
+        let mut roster = RosterCounts::default();
+        let a = player(&["legio", "arty"], Some("RDDT"), 2000);
+        let b = player(&["legio"], Some("RDDT"), 1500);
+        roster.add(&a);
+        roster.add(&b);
+        assert_eq!((roster.size, roster.total_wn8, roster.tag("legio"), roster.clans["RDDT"]), (2, 3500, 2, 2));
+        roster.remove(&a);
+        assert_eq!((roster.size, roster.total_wn8, roster.tag("legio"), roster.tag("arty")), (1, 1500, 1, 0));
+        // stats refreshed after b joined
+        let refreshed = player(&["legio"], Some("RDDT"), 4000);
+        roster.remove(&refreshed);
+        assert_eq!((roster.size, roster.total_wn8), (0, 0));
+    }
+
+    #[test]
+    fn explains_broken_rules() {
+        let mut roster = RosterCounts::default();
+        roster.add(&player(&["legio"], Some("RDDT"), 2000));
+        roster.add(&player(&["legio"], Some("RDDT"), 2000));
+        let rules: Vec<Constraint> = ["max_tag:legio:2", "max_clan:2", "max_wn8:4000", "min_tag:arty:1"]
+            .iter()
+            .map(|r| r.parse().unwrap())
+            .collect();
+        assert!(check_all(&rules, &roster, 1, 0).is_ok());
+        let err = check_all(&rules, &roster, 0, 0).unwrap_err();
+        assert!(err.contains("arty"), "{}", err);
+        roster.add(&player(&["legio"], Some("RDDT"), 100));
+        let err = check_all(&rules, &roster, 5, 0).unwrap_err();
+        assert!(err.contains("legio"), "{}", err);
+        let err = rules[1].check(&roster, 5, 0).unwrap_err();
+        assert!(err.contains("RDDT"), "{}", err);
+        let err = rules[2].check(&roster, 5, 0).unwrap_err();
+        assert!(err.contains("4100"), "{}", err);
+    }
+
+    #[test]
+    fn wn8_caps_leave_room_for_open_slots() {
+        let mut roster = RosterCounts::default();
+        roster.add(&player(&[], None, 3000));
+        roster.add(&player(&[], None, 2500));
+        let sum: Constraint = "max_wn8:6000".parse().unwrap();
+        assert!(sum.check(&roster, 1, 500).is_ok());
+        let err = sum.check(&roster, 2, 500).unwrap_err();
+        assert!(err.contains("no room"), "{}", err);
+        let average: Constraint = "max_wn8:avg:2000".parse().unwrap();
+        assert_eq!(average.to_string(), "max_wn8:avg:2000");
+        assert_eq!(wn8_space(&[sum.clone(), average.clone()], &roster, 3), Some(500));
+        assert!(average.check(&roster, 1, 500).is_ok());
+        roster.add(&player(&[], None, 1000));
+        assert!(sum.check(&roster, 0, 0).is_err());
+        let err = average.check(&roster, 0, 0).unwrap_err();
+        assert!(err.contains("6500"), "{}", err);
+        assert!(average.check(&roster, 1, 1500).is_ok());
+        assert!(average.check(&roster, 1, 1501).is_err());
+        let huge = Constraint::MaxAverageWn8 { cap: u32::MAX };
+        assert!(huge.check(&roster, u32::MAX - 3, 0).is_ok());
+        assert!("max_wn8:median:2000".parse::<Constraint>().is_err());
+    }
+}
@@ -0,0 +1,59 @@
+/// Why a draft action was refused. These are the user's mistake, commands
+/// return them and `on_error` turns them into an ephemeral reply
+#[derive(Clone, Debug, PartialEq)]
+pub enum DraftError {
+    NoDraftRunning,
+    /// The real draft was already started
+    DraftRunning,
+    NoAuctionRunning,
+    /// A mock draft holds the pick and bid commands
+    MockRunning,
+    NotCaptain,
+    NotYourTurn,
+    NominationClosed,
+    NothingNominated,
+    PlayerUnavailable(String),
+    UnknownPlayer(String),
+    UnderTopBid,
+    BelowMinimum { player: String, min_price: u32 },
+    InsufficientFunds { max_bid: u32 },
+    RosterFull,
+    /// A tier, constraint or salary cap rule, with the rule's explanation
+    RuleViolation(String),
+    AlreadyCaptain,
+    CaptainTooExpensive(u32),
+    /// A captain without a pool player can't share a name with one
+    CaptainNameTaken(String),
+    NotOnTeam(String),
+    /// A trade would leave this captain's balance below zero
+    TradeUnaffordable(String),
+}
+
+impl std::fmt::Display for DraftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DraftError::NoDraftRunning => write!(f, "No Draft Running"),
+            DraftError::DraftRunning => write!(f, "The draft is already running"),
+            DraftError::NoAuctionRunning => write!(f, "No Auction Running"),
+            DraftError::MockRunning => write!(f, "A mock draft is running"),
+            DraftError::NotCaptain => write!(f, "Not in captain list"),
+            DraftError::NotYourTurn => write!(f, "You not da captain blud"),
+            DraftError::NominationClosed => write!(f, "Only pick before round starts"),
+            DraftError::NothingNominated => write!(f, "No player has been nominated yet"),
+            DraftError::PlayerUnavailable(name) => write!(f, "{} is not available", name),
+            DraftError::UnknownPlayer(name) => write!(f, "No player named {}", name),
+            DraftError::UnderTopBid => write!(f, "Under current top bid"),
+            DraftError::BelowMinimum { player, min_price } => write!(f, "Minimum bid for {} is:{}", player, min_price),
+            DraftError::InsufficientFunds { max_bid } => write!(f, "Not enough funds, Your max bid is:{}", max_bid),
+            DraftError::RosterFull => write!(f, "Your roster is full"),
+            DraftError::RuleViolation(reason) => f.write_str(reason),
+            DraftError::AlreadyCaptain => write!(f, "Already Added this captain"),
+            DraftError::CaptainTooExpensive(cost) => write!(f, "A captain value of ${} leaves too little to fill the team", cost),
+            DraftError::CaptainNameTaken(name) => write!(f, "{} is a pool player, pick them as the captain's player or use another name", name),
+            DraftError::NotOnTeam(name) => write!(f, "{} is not on a team", name),
+            DraftError::TradeUnaffordable(name) => write!(f, "{} can't cover their side of the trade", name),
+        }
+    }
+}
+
+impl std::error::Error for DraftError {}
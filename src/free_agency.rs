@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::auction::wn8_floor;
+use crate::{add_player_to_captain, Captain, Config, Player};
+
+pub struct FreeAgencyBid {
+    pub captain: Arc<Mutex<Captain>>,
+    pub player: Arc<Mutex<Player>>,
+    pub amount: u32,
+}
+
+/// Sealed bids on leftover players, all resolved at once when the window closes
+#[derive(Default)]
+pub struct FreeAgency {
+    pub open: bool,
+    pub bids: Vec<FreeAgencyBid>,
+}
+
+pub struct Signing {
+    pub captain: String,
+    pub player: String,
+    pub amount: u32,
+}
+
+impl FreeAgency {
+    /// Replaces the captain's earlier bid on the same player, an amount of 0
+    /// just withdraws it
+    pub fn place_bid(&mut self, captain: Arc<Mutex<Captain>>, player: Arc<Mutex<Player>>, amount: u32) {
+        self.bids.retain(|b| !(Arc::ptr_eq(&b.captain, &captain) && Arc::ptr_eq(&b.player, &player)));
+        if amount > 0 {
+            self.bids.push(FreeAgencyBid { captain, player, amount });
+        }
+    }
+
+    /// Highest bid wins each player, earlier bids win ties. A bid is skipped
+    /// when the player is gone, it's under the player's tier minimum, the
+    /// captain's roster is full, the player
+    /// breaks a roster constraint or the captain can no longer pay for it
+    /// after earlier signings. `players` is the pool, for the WN8 left to
+    /// fill the rest of a capped roster
+    pub async fn resolve(&mut self, config: &Config, players: &[Arc<Mutex<Player>>], round: u32) -> Vec<Signing> {
+        self.open = false;
+        let mut bids = std::mem::take(&mut self.bids);
+        bids.sort_by_key(|b| std::cmp::Reverse(b.amount));
+        let mut signings = Vec::new();
+        for bid in bids {
+            let floor = wn8_floor(players, Some(&bid.player)).await;
+            let mut c1 = bid.captain.lock().await;
+            let p1 = bid.player.lock().await;
+            if p1.picked {
+                continue;
+            }
+            if bid.amount < config.min_price(p1.tier.as_deref())
+                || c1.players.len() as u32 >= config.team_size
+                || c1.balance < bid.amount
+                || c1.check_player(config, &p1, floor).is_err()
+            {
+                continue;
+            }
+            drop(p1);
+            c1.balance -= bid.amount;
+            let captain = c1.name.clone();
+            drop(c1);
+            add_player_to_captain(bid.captain.clone(), bid.player.clone(), bid.amount, round).await;
+            signings.push(Signing {
+                captain,
+                player: bid.player.lock().await.name.clone(),
+                amount: bid.amount,
+            });
+        }
+        signings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captain(id: u64, balance: u32) -> Arc<Mutex<Captain>> {
+        Arc::new(Mutex::new(Captain::new(id, format!("c{}", id), balance)))
+    }
+
+    fn player(name: &str) -> Arc<Mutex<Player>> {
+        Arc::new(Mutex::new(Player::new(name.to_string(), Vec::new())))
+    }
+
+    #[tokio::test]
+    async fn highest_bid_wins_and_ties_go_to_first() {
+        let (a, b) = (captain(1, 50), captain(2, 50));
+        let (x, y) = (player("x"), player("y"));
+        let mut fa = FreeAgency::default();
+        fa.place_bid(a.clone(), x.clone(), 20);
+        fa.place_bid(b.clone(), x.clone(), 25);
+        fa.place_bid(a.clone(), y.clone(), 15);
+        fa.place_bid(b.clone(), y.clone(), 15);
+        let signings = fa.resolve(&Config::default(), &[x.clone(), y.clone()], 9).await;
+        assert_eq!(signings.len(), 2);
+        assert_eq!(x.lock().await.team.as_ref().map(|t| Arc::ptr_eq(t, &b)), Some(true));
+        assert_eq!(y.lock().await.team.as_ref().map(|t| Arc::ptr_eq(t, &a)), Some(true));
+        assert_eq!(a.lock().await.balance, 35);
+        assert_eq!(b.lock().await.balance, 25);
+        assert_eq!(x.lock().await.purchase.map(|p| p.round), Some(9));
+    }
+
+    #[tokio::test]
+    async fn respects_roster_size_and_balance() {
+        let a = captain(1, 30);
+        let b = captain(2, 100);
+        let (x, y, z) = (player("x"), player("y"), player("z"));
+        let mut fa = FreeAgency::default();
+        fa.place_bid(a.clone(), x.clone(), 20);
+        fa.place_bid(a.clone(), y.clone(), 20);
+        fa.place_bid(b.clone(), y.clone(), 10);
+        fa.place_bid(b.clone(), z.clone(), 5);
+        fa.place_bid(b.clone(), x.clone(), 1);
+        fa.place_bid(b.clone(), x.clone(), 0);
+        let signings = fa.resolve(&Config { team_size: 1, ..Config::default() }, &[x.clone(), y.clone(), z.clone()], 9).await;
+        let names: Vec<(String, String)> = signings.into_iter().map(|s| (s.captain, s.player)).collect();
+        assert_eq!(names, vec![
+            (String::from("c1"), String::from("x")),
+            (String::from("c2"), String::from("y")),
+        ]);
+        assert!(!z.lock().await.picked);
+        assert_eq!(a.lock().await.balance, 10);
+    }
+
+    #[tokio::test]
+    async fn bids_under_the_tier_minimum_lose() {
+        let config = Config {
+            tiers: vec![crate::Tier { name: String::from("s"), min_price: 30, min_count: 0, max_count: 2 }],
+            ..Config::default()
+        };
+        let (a, b) = (captain(1, 100), captain(2, 100));
+        let (x, y) = (player("x"), player("y"));
+        for p in [&x, &y] {
+            p.lock().await.tier = Some(String::from("s"));
+        }
+        let mut fa = FreeAgency::default();
+        fa.place_bid(a.clone(), x.clone(), 25);
+        fa.place_bid(b.clone(), y.clone(), 30);
+        let signings = fa.resolve(&config, &[x.clone(), y.clone()], 9).await;
+        let names: Vec<(String, String)> = signings.into_iter().map(|s| (s.captain, s.player)).collect();
+        assert_eq!(names, vec![(String::from("c2"), String::from("y"))]);
+        assert!(!x.lock().await.picked);
+        assert_eq!(a.lock().await.balance, 100);
+    }
+}
@@ -0,0 +1,821 @@
+use poise::serenity_prelude::CreateEmbed;
+use tokio::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use constraints::{Constraint, RosterCounts};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub mod auction;
+pub mod bots;
+pub mod commands;
+pub mod constraints;
+pub mod error;
+pub mod free_agency;
+pub mod report;
+pub mod runner;
+pub mod simulate;
+pub mod trade;
+pub mod wargaming;
+pub mod wn8;
+
+pub const PLAYERS_PATH: &str = "/home/zray/code/auction2025/src/players.csv";
+pub const EXPECTED_VALUES_PATH: &str = "/home/zray/code/auction2025/src/expected_wn8.json";
+pub const TANK_STATS_PATH: &str = "/home/zray/code/auction2025/src/tank_stats.csv";
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+pub struct DraftState {
+    pub draft_started: bool,
+    pub draft_finished: bool,
+    pub current_round: u32,
+    pub nominated_player: Option<Arc<Mutex<Player>>>,
+    pub round_captain: Option<Arc<Mutex<Captain>>>,
+    pub bid_placed: bool,
+    /// Bids on the current lot after the nomination
+    pub bids: u32,
+    pub starting_bid: u32,
+    pub current_bid: u32,
+    pub current_winner: Option<Arc<Mutex<Captain>>>,
+    pub time: u32,
+    /// Holds the countdown, bids are still accepted
+    pub paused: bool,
+}
+
+impl Default for DraftState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DraftState {
+    pub fn new() -> Self {
+        Self {
+            draft_started: false,
+            draft_finished: false,
+            current_round: 0,
+            nominated_player: None,
+            round_captain: None,
+            bid_placed: false,
+            bids: 0,
+            starting_bid: 0,
+            current_bid: 0,
+            current_winner: None,
+            time: 0,
+            paused: false,
+        }
+    }
+
+    /// Opens round `round` for `captain` to nominate
+    pub fn start_round(&mut self, round: u32, captain: Arc<Mutex<Captain>>, config: &Config) {
+        self.current_round = round;
+        self.nominated_player = None;
+        self.starting_bid = config.min_bid;
+        self.current_bid = config.min_bid;
+        self.round_captain = Some(captain);
+        self.current_winner = None;
+        self.bid_placed = false;
+        self.bids = 0;
+        self.time = config.round_time;
+    }
+}
+
+
+
+#[derive(Clone)]
+pub struct Config {
+    pub min_bid: u32,
+    pub starting_balance: u32,
+    pub team_size: u32,
+    pub round_time: u32,
+    pub bid_add_time: u32,
+    pub dm_players: bool,
+    pub free_agency_time: u32,
+    pub trade_approval: bool,
+    pub captain_cost: CaptainCost,
+    pub tiers: Vec<Tier>,
+    pub constraints: Vec<Constraint>,
+}
+
+/// A group of players with its own minimum price and per team quota
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tier {
+    pub name: String,
+    pub min_price: u32,
+    pub min_count: u32,
+    pub max_count: u32,
+}
+
+impl Config {
+    /// Sets a setting by its field name, for command line front ends
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let number = || value.parse::<u32>().map_err(|_| format!("{} needs a number, got {}", key, value));
+        match key {
+            "min_bid" => self.min_bid = number()?,
+            "starting_balance" => self.starting_balance = number()?,
+            "team_size" => self.team_size = number()?,
+            "round_time" => self.round_time = number()?,
+            "bid_add_time" => self.bid_add_time = number()?,
+            "free_agency_time" => self.free_agency_time = number()?,
+            "captain_cost" => self.captain_cost = value.parse()?,
+            "constraint" => self.constraints.push(value.parse()?),
+            _ => return Err(format!("Unknown setting {}", key)),
+        }
+        Ok(())
+    }
+
+    pub fn tier(&self, name: Option<&str>) -> Option<&Tier> {
+        let name = name?;
+        self.tiers.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Lowest allowed price for a player of this tier
+    pub fn min_price(&self, tier: Option<&str>) -> u32 {
+        self.tier(tier).map(|t| t.min_price).unwrap_or(self.min_bid)
+    }
+
+    /// Money needed to fill `open_slots` while meeting every tier minimum,
+    /// None when the minimums need more slots than are open or the sum
+    /// doesn't fit in a balance
+    pub fn reserve(&self, tier_counts: &HashMap<String, u32>, open_slots: u32) -> Option<u32> {
+        let mut reserve: u64 = 0;
+        let mut required: u64 = 0;
+        for tier in self.tiers.iter() {
+            let missing = tier.min_count.saturating_sub(*tier_counts.get(&tier.name).unwrap_or(&0)) as u64;
+            reserve = reserve.checked_add(missing.checked_mul(tier.min_price as u64)?)?;
+            required = required.checked_add(missing)?;
+        }
+        let open_slots = open_slots as u64;
+        if required > open_slots {
+            return None;
+        }
+        let rest = (open_slots - required).checked_mul(self.min_bid as u64)?;
+        u32::try_from(reserve.checked_add(rest)?).ok()
+    }
+}
+
+/// Turns a playing captain's WN8 into the amount taken off their budget
+#[derive(Clone, Debug, PartialEq)]
+pub enum CaptainCost {
+    /// `rate` per 100 WN8 above `base`
+    Linear { base: u32, rate: u32 },
+    /// Cost of the highest WN8 threshold the captain reaches, sorted by threshold
+    Table(Vec<(u32, u32)>),
+}
+
+impl CaptainCost {
+    pub fn cost(&self, wn8: u32) -> u32 {
+        match self {
+            // in u64 so a big rate can't wrap, anything past u32 is unaffordable anyway
+            CaptainCost::Linear { base, rate } => {
+                let cost = wn8.saturating_sub(*base) as u64 * *rate as u64 / 100;
+                u32::try_from(cost).unwrap_or(u32::MAX)
+            }
+            CaptainCost::Table(table) => table.iter()
+                .rev()
+                .find(|(threshold, _)| wn8 >= *threshold)
+                .map(|(_, cost)| *cost)
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl std::str::FromStr for CaptainCost {
+    type Err = String;
+
+    /// `linear:<base>:<rate>` or `table:<wn8>=<cost>,<wn8>=<cost>,..`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid captain cost `{}`, use linear:<base>:<rate> or table:<wn8>=<cost>,..", s);
+        match s.split_once(':') {
+            Some(("linear", rest)) => {
+                let (base, rate) = rest.split_once(':').ok_or_else(invalid)?;
+                Ok(CaptainCost::Linear {
+                    base: base.trim().parse().map_err(|_| invalid())?,
+                    rate: rate.trim().parse().map_err(|_| invalid())?,
+                })
+            }
+            Some(("table", rest)) => {
+                let mut table = Vec::new();
+                for entry in rest.split(',') {
+                    let (wn8, cost) = entry.split_once('=').ok_or_else(invalid)?;
+                    table.push((
+                        wn8.trim().parse().map_err(|_| invalid())?,
+                        cost.trim().parse().map_err(|_| invalid())?,
+                    ));
+                }
+                table.sort_unstable();
+                Ok(CaptainCost::Table(table))
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for CaptainCost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptainCost::Linear { base, rate } => write!(f, "linear:{}:{}", base, rate),
+            CaptainCost::Table(table) => {
+                let entries: Vec<String> = table.iter().map(|(w, c)| format!("{}={}", w, c)).collect();
+                write!(f, "table:{}", entries.join(","))
+            }
+        }
+    }
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            min_bid: 10,
+            starting_balance: 200,
+            team_size: 8,
+            round_time: 20,
+            bid_add_time: 5,
+            dm_players: false,
+            free_agency_time: 0,
+            trade_approval: false,
+            captain_cost: CaptainCost::Linear { base: 0, rate: 0 },
+            tiers: Vec::new(),
+            constraints: vec![Constraint::MaxTag { tag: String::from("legio"), count: 2 }],
+        }
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut config_str = String::new();
+        config_str += &format!("Min Bid: {}", self.min_bid);
+        config_str.push('\n');
+        config_str += &format!("Starting Balance: {}", self.starting_balance);
+        config_str.push('\n');
+        config_str += &format!("Team Size: {}", self.team_size);
+        config_str.push('\n');
+        config_str += &format!("Round Time: {}", self.round_time);
+        config_str.push('\n');
+        config_str += &format!("Bid add team: {}", self.bid_add_time);
+        config_str.push('\n');
+        config_str += &format!("DM Players: {}", self.dm_players);
+        config_str.push('\n');
+        config_str += &format!("Free Agency Time: {}", self.free_agency_time);
+        config_str.push('\n');
+        config_str += &format!("Trade Approval: {}", self.trade_approval);
+        config_str.push('\n');
+        config_str += &format!("Captain Cost: {}", self.captain_cost);
+        config_str.push('\n');
+        for tier in self.tiers.iter() {
+            config_str += &format!("Tier {}: min ${}, {}-{} per team",
+                tier.name,
+                tier.min_price,
+                tier.min_count,
+                tier.max_count,
+            );
+            config_str.push('\n');
+        }
+        for constraint in self.constraints.iter() {
+            config_str += &format!("Constraint: {}", constraint);
+            config_str.push('\n');
+        }
+        f.write_str(&config_str)
+    }
+}
+pub async fn add_player_to_captain(
+    captain:Arc<Mutex<Captain>>,
+    player: Arc<Mutex<Player>>,
+    price: u32,
+    round: u32,
+) {
+    // captains are always locked before their players
+    let mut c1 = captain.lock().await;
+    let mut p1 = player.lock().await;
+    c1.count_player(&p1);
+    p1.picked = true;
+    p1.purchase = Some(Purchase {
+        price,
+        round,
+        bought_at: unix_now(),
+        keeper: false,
+    });
+    p1.team = Some(captain.clone());
+    drop(p1);
+    c1.players.push(player);
+}
+
+/// Puts a keeper on `captain`'s roster before the draft, marked so it can be
+/// released again
+pub async fn add_keeper(captain: Arc<Mutex<Captain>>, player: Arc<Mutex<Player>>, price: u32) {
+    add_player_to_captain(captain, player.clone(), price, 0).await;
+    if let Some(purchase) = player.lock().await.purchase.as_mut() {
+        purchase.keeper = true;
+    }
+}
+
+pub struct BidRecord {
+    pub round: u32,
+    pub player: String,
+    pub amount: u32,
+}
+
+pub struct Captain {
+    pub discord_id: u64,
+    pub name: String,
+    pub players: Vec<Arc<Mutex<Player>>>,
+    pub balance: u32,
+    pub counts: RosterCounts,
+    pub bid_history: Vec<BidRecord>,
+}
+impl Captain {
+    pub fn new(id: u64, name: String, bal: u32) -> Self {
+        Self {
+            discord_id: id,
+            name,
+            players: Vec::new(),
+            balance: bal,
+            counts: RosterCounts::default(),
+            bid_history: Vec::new(),
+        }
+    }
+
+    /// Updates roster counts for a player joining the roster
+    pub fn count_player(&mut self, player: &Player) {
+        self.counts.add(player);
+    }
+
+    /// Updates roster counts for a player leaving the roster
+    pub fn uncount_player(&mut self, player: &Player) {
+        self.counts.remove(player);
+    }
+
+    /// Rebuilds roster counts, for when player stats change after purchase
+    pub async fn recount(&mut self) {
+        let mut counts = RosterCounts::default();
+        for player in self.players.iter() {
+            counts.add(&*player.lock().await);
+        }
+        self.counts = counts;
+    }
+
+    fn counts_with(&self, player: Option<&Player>) -> RosterCounts {
+        let mut counts = self.counts.clone();
+        if let Some(player) = player {
+            counts.add(player);
+        }
+        counts
+    }
+
+    /// Whether buying `player` keeps the roster within tier quotas and the
+    /// configured constraints, the error names the rule that blocks it.
+    /// `wn8_floor` is the weakest player left for the other open slots
+    pub fn check_player(&self, config: &Config, player: &Player, wn8_floor: u32) -> Result<(), String> {
+        if let Some(tier) = config.tier(player.tier.as_deref()) {
+            if self.counts.tier(&tier.name) >= tier.max_count {
+                return Err(format!("You already have {} tier {} players", tier.max_count, tier.name));
+            }
+        }
+        let counts = self.counts_with(Some(player));
+        let open_slots = config.team_size.saturating_sub(counts.size);
+        if config.reserve(&counts.tiers, open_slots).is_none() {
+            return Err(String::from("Your remaining slots are needed for tier minimums"));
+        }
+        constraints::check_all(&config.constraints, &counts, open_slots, wn8_floor)
+    }
+
+    /// Most the captain can pay for `player` and still fill the rest of the
+    /// roster, including tier minimums
+    pub fn get_max_bid(&self, config: &Config, player: Option<&Player>) -> u32 {
+        let slots_left = config.team_size.saturating_sub(self.players.len() as u32);
+        let open_slots = slots_left.saturating_sub(1);
+        match config.reserve(&self.counts_with(player).tiers, open_slots) {
+            Some(reserve) => self.balance.saturating_sub(reserve),
+            None => 0,
+        }
+    }
+
+    pub async fn to_string(&self) -> String {
+        let mut player_str = String::from("");
+        if self.players.is_empty() {
+            player_str = String::from("None");
+        }
+        let mut names = Vec::new();
+        for player in self.players.iter() {
+            names.push(player.lock().await.name.clone());
+        }
+        if !names.is_empty() {
+            player_str = names.join(", ");
+        }
+        format!("Name: {}\nBalance: {}\n Players: {}",
+            self.name,
+            self.balance,
+            player_str,
+            )
+    }
+
+}
+
+pub struct Player {
+    pub name: String,
+    pub discord_id: Option<u64>,
+    pub tags: Vec<String>,
+    pub clan: Option<String>,
+    pub overall_wn8: u32,
+    pub recent_wn8: u32,
+    pub notes: Option<String>,
+    pub tier: Option<String>,
+    pub team: Option<Arc<Mutex<Captain>>>,
+    pub picked: bool,
+    pub purchase: Option<Purchase>,
+}
+
+/// What a captain paid for a player, `bought_at` is unix seconds
+#[derive(Clone, Copy)]
+pub struct Purchase {
+    pub price: u32,
+    pub round: u32,
+    pub bought_at: u64,
+    /// Kept before the draft with `/keeper`, only these can be released again
+    pub keeper: bool,
+}
+
+
+impl Player {
+    pub fn new(name: String, tags: Vec<String>) -> Self {
+        Self {
+            name,
+            discord_id: None,
+            tags,
+            clan: None,
+            overall_wn8: 0,
+            recent_wn8: 0,
+            notes: None,
+            tier: None,
+            team: None,
+            picked: false,
+            purchase: None,
+        }
+    }
+
+    /// Discord mention if the player is linked, plain name otherwise
+    pub fn mention(&self) -> String {
+        match self.discord_id {
+            Some(id) => format!("<@{}>", id),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Name with a WN8 bracket marker, for team lists
+    pub fn team_line(&self) -> String {
+        format!("{} {} ({})", get_wn8_emoji(self.recent_wn8), self.name, self.recent_wn8)
+    }
+
+    /// Team line with the price paid
+    pub fn roster_line(&self) -> String {
+        match self.purchase {
+            Some(purchase) => format!("{} — ${}", self.team_line(), purchase.price),
+            None => self.team_line(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn card(&self) -> String {
+        format!("Name: `{}`\nTier: `{}`\nTags: `{}`\nClan: `{}`\nWN8: `{}` Recent: `{}`\nDiscord: {}\nNotes: {}",
+            self.name,
+            self.tier.as_deref().unwrap_or("None"),
+            if self.tags.is_empty() { String::from("None") } else { self.tags.join(", ") },
+            self.clan.as_deref().unwrap_or("None"),
+            self.overall_wn8,
+            self.recent_wn8,
+            self.discord_id.map(|id| format!("<@{}>", id)).unwrap_or(String::from("None")),
+            self.notes.as_deref().unwrap_or("None"),
+        )
+    }
+}
+
+impl std::fmt::Display for Player {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Reads the player pool, columns are name, tags separated by `;`, an optional discord id,
+/// notes, tier and clan.
+/// WN8 is filled in when both the expected values and tank stats files exist
+pub fn load_players(path: &str) -> Result<Vec<Arc<Mutex<Player>>>, csv::Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)?;
+    let expected = wn8::ExpectedValues::load(EXPECTED_VALUES_PATH).ok();
+    let tank_stats = wn8::load_tank_stats(TANK_STATS_PATH).ok();
+    let mut players: Vec<Arc<Mutex<Player>>> = Vec::new();
+    for result in rdr.records() {
+        let record = result?;
+        let name = record.get(0).unwrap_or_default();
+        let tags = record.get(1)
+            .map(|t| t.split(';')
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect())
+            .unwrap_or_default();
+        let mut player = Player::new(
+            name.to_string(),
+            tags,
+        );
+        player.discord_id = record.get(2)
+            .and_then(|id| id.trim().parse::<u64>().ok());
+        player.notes = record.get(3)
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty());
+        player.tier = record.get(4)
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty());
+        player.clan = record.get(5)
+            .map(|c| c.trim().to_string())
+            .filter(|c| !c.is_empty());
+        if let (Some(expected), Some(tank_stats)) = (&expected, &tank_stats) {
+            if let Some(stats) = tank_stats.get(name) {
+                player.overall_wn8 = expected.calculate(&stats.overall).round() as u32;
+                player.recent_wn8 = expected.calculate(&stats.recent).round() as u32;
+            }
+        }
+        players.push(Arc::new(Mutex::new(player)));
+
+    }
+    Ok(players)
+}
+
+/// Spells every player's tier the way the config does. Tier lookups ignore
+/// case but roster counts are keyed by the name, so "s" from the CSV has to
+/// become the configured "S" to count towards its quota
+pub async fn normalize_tiers(config: &Config, players: &[Arc<Mutex<Player>>]) {
+    for player in players {
+        let mut p1 = player.lock().await;
+        if let Some(tier) = config.tier(p1.tier.as_deref()) {
+            if p1.tier.as_deref() != Some(tier.name.as_str()) {
+                p1.tier = Some(tier.name.clone());
+            }
+        }
+    }
+}
+
+pub fn import_players() -> Result<Vec<Arc<Mutex<Player>>>, csv::Error> {
+    load_players(PLAYERS_PATH)
+}
+
+
+pub fn get_wn8_color(wn8: u32) -> i32 {
+    match wn8 {
+        0 => 0x808080,
+        1..=300 => 0x930D0D,
+        301..=450 => 0xCD3333,
+        451..=650 => 0xCC7A00,
+        651..=900 => 0xCCB800,
+        901..=1200 => 0x849B24,
+        1201..=1600 => 0x4D7326,
+        1601..=2000 => 0x4099BF,
+        2001..=2450 => 0x3972C6,
+        2451..=2900 => 0x6844d4,
+        2901..=3400 => 0x522b99,
+        3401..=4000 => 0x411d73,
+        4001..=4700 => 0x310d59,
+        4701..=u32::MAX => 0x24073d,
+    }
+}
+
+/// Embed text can't be colored, so team lists use a square per WN8 bracket
+pub fn get_wn8_emoji(wn8: u32) -> &'static str {
+    match wn8 {
+        0 => "⬜",
+        1..=450 => "🟥",
+        451..=650 => "🟧",
+        651..=900 => "🟨",
+        901..=1600 => "🟩",
+        1601..=2450 => "🟦",
+        2451..=u32::MAX => "🟪",
+    }
+}
+/// Title and fields of a draft embed, so the terminal can print what Discord shows
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Board {
+    pub title: String,
+    /// Name, value and whether the field is inline
+    pub fields: Vec<(String, String, bool)>,
+    pub colour: Option<i32>,
+}
+
+impl Board {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn field(mut self, name: impl Into<String>, value: impl Into<String>, inline: bool) -> Self {
+        self.fields.push((name.into(), value.into(), inline));
+        self
+    }
+
+    pub fn colour(mut self, colour: i32) -> Self {
+        self.colour = Some(colour);
+        self
+    }
+
+    pub fn embed(&self) -> CreateEmbed {
+        let mut embed = CreateEmbed::default().title(&self.title);
+        for (name, value, inline) in self.fields.iter() {
+            embed = embed.field(name, value, *inline);
+        }
+        if let Some(colour) = self.colour {
+            embed = embed.colour(colour);
+        }
+        embed
+    }
+}
+
+impl std::fmt::Display for Board {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "== {} ==", self.title)?;
+        for (name, value, _) in self.fields.iter() {
+            writeln!(f, "{}", name)?;
+            for line in value.lines() {
+                writeln!(f, "  {}", line.replace('`', ""))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub async fn make_final_draft_embed(captains: Vec<Arc<Mutex<Captain>>>) -> CreateEmbed {
+    final_draft_board(captains).await.embed()
+}
+
+/// Rosters and team summaries once the draft is over
+pub async fn final_draft_board(captains: Vec<Arc<Mutex<Captain>>>) -> Board {
+    let mut embed = Board::new("Draft");
+    let mut teams = Vec::new();
+    for captain in captains {
+        let c1 = captain.lock().await;
+        let mut player_str = String::from("");
+        for player in &c1.players {
+            let p1 = player.lock().await;
+            player_str.push_str(&p1.roster_line());
+            player_str.push('\n');
+        }
+        let summary = report::TeamSummary::new(&c1).await;
+        player_str.push_str(&summary.to_string());
+        teams.push(summary);
+        embed = embed.field(
+            c1.name.clone(),
+            player_str,
+            true
+        );
+    }
+    embed.field("Balance", report::balance_text(&teams), false)
+}
+
+/// Number of bids shown on the captain dashboard
+const DASHBOARD_BIDS: usize = 10;
+
+pub async fn captain_dashboard(captain: &Captain, config: &Config) -> CreateEmbed {
+    let slots_left = config.team_size.saturating_sub(captain.players.len() as u32);
+    let mut roster = String::new();
+    for player in &captain.players {
+        let p1 = player.lock().await;
+        roster += &p1.roster_line();
+        if let Some(purchase) = p1.purchase {
+            roster += &format!(" (R{}, <t:{}:t>)", purchase.round, purchase.bought_at);
+        }
+        roster.push('\n');
+    }
+    if roster.is_empty() {
+        roster = String::from("None");
+    }
+    let mut bids = String::new();
+    for record in captain.bid_history.iter().rev().take(DASHBOARD_BIDS) {
+        bids += &format!("Round {}: ${} on {}\n", record.round, record.amount, record.player);
+    }
+    if bids.is_empty() {
+        bids = String::from("None");
+    }
+    let mut budget = format!("Balance: `${}`\nMax Bid: `${}`\nSlots Left: `{}`",
+        captain.balance,
+        if slots_left > 0 { captain.get_max_bid(config, None) } else { 0 },
+        slots_left,
+    );
+    for constraint in config.constraints.iter() {
+        budget += &format!("\n{}", constraint.status(&captain.counts, config.team_size));
+    }
+    for tier in config.tiers.iter() {
+        budget += &format!("\nTier {}: `{}` (needs {}-{})",
+            tier.name,
+            captain.counts.tier(&tier.name),
+            tier.min_count,
+            tier.max_count,
+        );
+    }
+    CreateEmbed::default()
+        .title(format!("Captain {}", captain.name))
+        .field("Budget", budget, true)
+        .field("Roster", roster, true)
+        .field("Recent Bids", bids, false)
+}
+
+pub async fn generate_draft_embed(
+    draft_state: &DraftState,
+    captains: &[Arc<Mutex<Captain>>],
+    config: &Config,
+) -> CreateEmbed {
+    draft_board(draft_state, captains, config).await.embed()
+}
+
+/// Round, nominated player and bid info for the running draft
+pub async fn draft_board(
+    draft_state: &DraftState,
+    captains: &[Arc<Mutex<Captain>>],
+    config: &Config,
+) -> Board {
+    let mut embed = Board::new("Draft");
+    if let Some(captain) = &draft_state.round_captain {
+        let name = &captain.lock().await.name;
+        embed = embed.field(
+            "Round Info",
+            format!("Round: `{}`\nCaptain: '{}'\nTime Left: {}{}",
+                draft_state.current_round,
+                name,
+                draft_state.time,
+                if draft_state.paused { " (paused)" } else { "" },
+                ),
+                true
+        );
+    }
+    else {
+        
+        embed = embed.field(
+            "Round Info",
+            format!("Round: `{}`\nCaptain: '{}'",
+                draft_state.current_round,
+                "None",
+                ),
+                true
+        );
+    }
+    if let Some(player) = &draft_state.nominated_player {
+        let p1 = player.lock().await;
+        embed = embed
+            .field("Player info", p1.card(), true)
+            .colour(get_wn8_color(p1.recent_wn8));
+    }
+    else {
+        embed = embed.field(
+            "Player info",
+            format!("Name: `{}`\nRecent wn8: `{}`\nNotes: {}",
+                "None",
+                "0",
+                "None",
+            ),
+            true
+
+        );
+    }
+    if let Some(captain) = &draft_state.current_winner {
+        let c1 = captain.lock().await;
+        embed = embed.field(
+            "Bid Info",
+            format!("Starting Bid: `{}`\nCurrent Winner: `{}`\nCurrent Bid: `{}`",
+                draft_state.starting_bid,
+                c1.name,
+                draft_state.current_bid,
+
+            ),
+            true
+        );
+    }
+    else {
+        embed = embed.field(
+            "Bid Info",
+            format!("Starting Bid: `{}`\nCurrent Winner: `{}`\nCurrent Bid: `{}`",
+                draft_state.starting_bid,
+                "None",
+                draft_state.current_bid,
+
+            ),
+            true
+        );
+
+    }
+    let capped = config.constraints.iter().any(|c| c.wn8_limit(config.team_size).is_some());
+    if capped && !captains.is_empty() {
+        let mut space = String::new();
+        for captain in captains {
+            let c1 = captain.lock().await;
+            let left = constraints::wn8_space(&config.constraints, &c1.counts, config.team_size).unwrap_or(0);
+            space += &format!("{}: `{}`\n", c1.name, left);
+        }
+        embed = embed.field("Cap Space", space, false);
+    }
+    embed
+    
+}
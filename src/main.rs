@@ -1,176 +1,77 @@
-use poise::serenity_prelude::{self as serenity, CreateEmbed, EditMessage};
+use poise::serenity_prelude::{
+    self as serenity, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
+    CreateAttachment, CreateInteractionResponseMessage, CreateMessage, EditMessage,
+};
 use poise::CreateReply;
 use tokio::sync::Mutex;
 use std::sync::Arc;
-use rand::thread_rng;
-use rand::seq::SliceRandom;
 use tokio::time;
 use std::time::Duration;
+use tracing::{error, info, instrument, warn};
+use auction2025::constraints::Constraint;
+use auction2025::error::DraftError;
+use auction2025::runner::{CommandOutput, DraftOutput};
+use auction2025::{
+    add_keeper, auction, bots, captain_dashboard, commands, final_draft_board, free_agency,
+    import_players, make_final_draft_embed, normalize_tiers, report, runner, trade, unix_now, wargaming, wn8, Board,
+    Captain, Config, Error, Player, Purchase, Tier, EXPECTED_VALUES_PATH,
+};
+
+const WG_CACHE_DIR: &str = "/home/zray/code/auction2025/wg_cache";
+const RECENT_DAYS: u64 = 30;
+/// Where logs go when `LOG_DIR` is not set, relative to where the bot runs
+const DEFAULT_LOG_DIR: &str = "logs";
+/// Used when `RUST_LOG` is not set
+const DEFAULT_LOG_FILTER: &str = "warn,auction2025=info";
 
-type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a,UserData,Error>;
 
 
 pub struct UserData {
-    captains: Mutex<Vec<Arc<Mutex<Captain>>>>,
-    players: Mutex<Vec<Arc<Mutex<Player>>>>,
+    draft: Arc<auction::Session>,
+    /// Running mock draft, `pick` and `bid` go to it instead of the real one
+    mock: Mutex<Option<Arc<auction::Session>>>,
+    /// Last mock draft kept with `save`
+    mock_result: Mutex<Option<Arc<auction::Session>>>,
     config: Mutex<Config>,
-    draft_state: Mutex<DraftState>,
-}
-
-
-pub struct DraftState {
-    draft_started: bool,
-    current_round: u32,
-    nominated_player: Option<Arc<Mutex<Player>>>,
-    round_captain: Option<Arc<Mutex<Captain>>>,
-    bid_placed: bool,
-    starting_bid: u32,
-    current_bid: u32,
-    current_winner: Option<Arc<Mutex<Captain>>>,
-    time: u32,
-}
-
-impl DraftState {
-    pub fn new() -> Self {
-        Self {
-            draft_started: false,
-            current_round: 0,
-            nominated_player: None,
-            round_captain: None,
-            bid_placed: false,
-            starting_bid: 0,
-            current_bid: 0,
-            current_winner: None,
-            time: 0,
-        }
-    }
-}
-
-
-
-pub struct Config {
-    min_bid: u32,
-    starting_balance: u32,
-    team_size: u32,
-    round_time: u32,
-    bid_add_time: u32,
-    legio_limit: u32,
-}
-impl Config {
-    pub fn default() -> Self {
-        Self {
-            min_bid: 10,
-            starting_balance: 200,
-            team_size: 8,
-            round_time: 20,
-            bid_add_time: 5,
-            legio_limit: 2,
-        }
-    }
-    pub fn to_string(&self) -> String {
-        let mut config_str = String::new();
-        config_str += &format!("Min Bid: {}", self.min_bid);
-        config_str.push('\n');
-        config_str += &format!("Starting Balance: {}", self.starting_balance);
-        config_str.push('\n');
-        config_str += &format!("Team Size: {}", self.team_size);
-        config_str.push('\n');
-        config_str += &format!("Round Time: {}", self.round_time);
-        config_str.push('\n');
-        config_str += &format!("Bid add team: {}", self.bid_add_time);
-        config_str.push('\n');
-        config_str += &format!("Legio Limit: {}", self.legio_limit);
-        config_str.push('\n');
-        return config_str;
-
-    }
-}
-pub async fn add_player_to_captain(captain:Arc<Mutex<Captain>>, player: Arc<Mutex<Player>>) {
-    let mut p1 = player.lock().await;
-    let mut c1 = captain.lock().await;
-    if p1.is_legio {
-        c1.legio_count +=1;
-    }
-    p1.picked = true;
-    p1.team = Some(captain.clone());
-    drop(p1);
-    c1.players.push(player);
-}
-
-pub struct Captain {
-    discord_id: u64,
-    name: String,
-    players: Vec<Arc<Mutex<Player>>>,
-    balance: u32,
-    legio_count: u32,
+    free_agency: Mutex<free_agency::FreeAgency>,
+    trades: Mutex<Vec<trade::Trade>>,
+    next_trade_id: Mutex<u32>,
+    draft_log: Mutex<Vec<LogEntry>>,
 }
-impl Captain {
-    pub fn new(id: u64, name: String, bal: u32) -> Self {
-        Self {
-            discord_id: id,
-            name,
-            players: Vec::new(),
-            balance: bal,
-            legio_count: 0,
-        }
-    }
-    
-    pub fn get_max_bid(&self, config: &Config) -> u32 {
-        let slots_left = config.team_size - self.players.len() as u32;
-        return self.balance - slots_left*config.min_bid;
-
-    }
 
-    pub async fn to_string(&self) -> String {
-        let mut player_str = String::from("");
-        if self.players.len() == 0 {
-            player_str = String::from("None");
+impl UserData {
+    /// The mock draft while one runs, the real draft otherwise
+    pub async fn session(&self) -> Arc<auction::Session> {
+        match &*self.mock.lock().await {
+            Some(mock) => mock.clone(),
+            None => self.draft.clone(),
         }
-        for player in self.players.iter() {
-            let p1 = player.lock().await;
-            player_str = player_str + &p1.name.clone();
-        }
-        return format!("Name: {}\nBalance: {}\n Players: {}",
-            self.name,
-            self.balance,
-            player_str,
-            )
     }
-
 }
 
-pub struct Player {
-    name: String,
-    is_legio: bool,
-    recent_wn8: u32,
-    team: Option<Arc<Mutex<Captain>>>,
-    picked: bool,
+/// Something that changed a roster, `at` is unix seconds
+pub struct LogEntry {
+    at: u64,
+    text: String,
 }
 
-
-impl Player {
-    pub fn new(name: String, is_legio: bool) -> Self {
-        Self {
-            name,
-            is_legio,
-            recent_wn8: 0,
-            team: None,
-            picked: false,
-        }
-    }
-    pub fn to_string(&self) -> String {
-        return self.name.clone();
-    }
+pub async fn log_event(data: &UserData, text: String) {
+    data.draft_log.lock().await.push(LogEntry {
+        at: unix_now(),
+        text,
+    });
 }
 
+
 /// Displays Captains, Use Display Teams instead
 #[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
 async fn display_captains(
     ctx: Context<'_>,
 ) -> Result<(), Error> {
     let mut captain_str = String::from("Captains: \n");
-    let captains = ctx.data().captains.lock().await;
+    let captains = ctx.data().draft.captains.lock().await;
     for captain in captains.iter() {
         captain_str += &(captain.lock().await.name);
         captain_str += "\n";
@@ -180,6 +81,8 @@ async fn display_captains(
 }
 
 #[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+#[allow(clippy::too_many_arguments)]
 async fn config(
     ctx: Context<'_>,
     #[description = "Round Time"] round_time: Option<u32>,
@@ -187,121 +90,262 @@ async fn config(
     #[description = "Minimum Bid"] min_bid: Option<u32>,
     #[description = "Starting Balance"] starting_balance: Option<u32>,
     #[description = "Team Size"] team_size: Option<u32>,
-    #[description = "Legio Limit"] legio_limit: Option<u32>,
+    #[description = "DM players when they are drafted"] dm_players: Option<bool>,
+    #[description = "Free agency window after the draft in seconds, 0 to skip"] free_agency_time: Option<u32>,
+    #[description = "Trades need admin approval"] trade_approval: Option<bool>,
+    #[description = "Playing captain cost, linear:<base>:<rate> or table:<wn8>=<cost>,.."] captain_cost: Option<String>,
 
 ) ->Result<(), Error> {
-    let mut config = ctx.data().config.lock().await;
-    if let Some(rt) = round_time {
-        config.round_time = rt;
-    }
-    if let Some(bt) = bid_add_time {
-        config.bid_add_time = bt;
-    }
-    if let Some(mb) = min_bid {
-        config.min_bid = mb;
-    }
-    if let Some(sb) = starting_balance {
-        let draft_state = ctx.data().draft_state.lock().await;
-        if draft_state.draft_started {
+    let changes = commands::ConfigChanges {
+        round_time,
+        bid_add_time,
+        min_bid,
+        starting_balance,
+        team_size,
+        dm_players,
+        free_agency_time,
+        trade_approval,
+        captain_cost,
+    };
+    commands::config(&ctx.data().draft, &ctx.data().config, changes, &mut Replier(ctx)).await
+}
 
-            let _ = ctx.send(CreateReply::default()
-                .content("Cannot change starting balance after start")
-                .reply(true)
-                .ephemeral(true))
-                .await;
-            return Ok(())
-        }
-        config.starting_balance = sb;
-    }
-    if let Some(ts) = team_size {
-        let draft_state = ctx.data().draft_state.lock().await;
-        if draft_state.draft_started {
-            let _ = ctx.send(CreateReply::default()
-                .content("Cannot change team size after start")
-                .reply(true)
-                .ephemeral(true))
-                .await;
-            return Ok(())
-        }
-        config.team_size = ts;
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn display_teams(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    
+    let captains = ctx.data().draft.captains.lock().await;
+    let embed = make_final_draft_embed(captains.clone()).await;
+    let _ = ctx.send(CreateReply::default().embed(embed)).await;
+    Ok(())
+}
+
+/// Shows your balance, max bid and roster as a captain
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn my_captain(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let Some(captain) = find_captain(ctx.data(), u64::from(ctx.author().id)).await else {
+        let _ = ctx.send(CreateReply::default()
+            .content("Not in captain list")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let ctx_id = ctx.id();
+    let refresh_id = format!("{}refresh", ctx_id);
+    let config = ctx.data().config.lock().await.clone();
+    let embed = captain_dashboard(&*captain.lock().await, &config).await;
+    ctx.send(CreateReply::default()
+        .embed(embed)
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&refresh_id).label("Refresh"),
+        ])])
+        .ephemeral(true))
+        .await?;
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id == format!("{}refresh", ctx_id))
+        .timeout(Duration::from_secs(15 * 60))
+        .await
+    {
+        let config = ctx.data().config.lock().await.clone();
+        let embed = captain_dashboard(&*captain.lock().await, &config).await;
+        press.create_response(ctx, CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().embed(embed)
+        )).await?;
     }
-    if let Some(ll) = legio_limit {
-        let draft_state = ctx.data().draft_state.lock().await;
-        if draft_state.draft_started {
-            let _ = ctx.say(
-                "This might not function properly after draft start. Restart draft if early."
-                );
+    Ok(())
+}
+
+/// Exports every roster with prices as a csv file
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn export_teams(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let captains = ctx.data().draft.captains.lock().await.clone();
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    wtr.write_record(["captain", "player", "price", "round", "bought_at", "recent_wn8"])?;
+    for captain in captains.iter() {
+        let c1 = captain.lock().await;
+        let mut total = 0;
+        for player in &c1.players {
+            let p1 = player.lock().await;
+            let purchase = p1.purchase.unwrap_or(Purchase { price: 0, round: 0, bought_at: 0, keeper: false });
+            total += purchase.price;
+            wtr.write_record([
+                c1.name.clone(),
+                p1.name.clone(),
+                purchase.price.to_string(),
+                purchase.round.to_string(),
+                purchase.bought_at.to_string(),
+                p1.recent_wn8.to_string(),
+            ])?;
         }
-        config.legio_limit = ll;
+        wtr.write_record([c1.name.clone(), String::from("TOTAL"), total.to_string(), String::new(), String::new(), String::new()])?;
     }
-    let _ = ctx.say(config.to_string()).await;
-    
-    return Ok(())
-
+    let data = wtr.into_inner()?;
+    ctx.send(CreateReply::default()
+        .content("Draft export")
+        .attachment(CreateAttachment::bytes(data, "teams.csv")))
+        .await?;
+    Ok(())
 }
 
+/// Shows team strength and how balanced the teams are
 #[poise::command(slash_command)]
-async fn display_teams(
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn team_report(
     ctx: Context<'_>,
 ) -> Result<(), Error> {
-    
-    let captains = ctx.data().captains.lock().await;
-    let embed = make_final_draft_embed(captains.to_vec()).await;
+    let captains = ctx.data().draft.captains.lock().await.clone();
+    let embed = report::team_report_embed(&captains).await;
     let _ = ctx.send(CreateReply::default().embed(embed)).await;
     Ok(())
 }
 
-/// Displays players (for debugging do not use) DO NOT USE
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum PoolSort {
+    Name,
+    #[name = "WN8"]
+    Wn8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum PoolStatus {
+    Unpicked,
+    Picked,
+    All,
+}
+
+/// Players shown per page of /pool
+const POOL_PAGE_SIZE: usize = 15;
+
+fn pool_embed(pages: &[String], page: usize, title: &str) -> CreateEmbed {
+    CreateEmbed::default()
+        .title(title)
+        .description(&pages[page])
+        .footer(serenity::CreateEmbedFooter::new(format!("Page {}/{}", page + 1, pages.len())))
+}
+
+/// Browse the player pool
 #[poise::command(slash_command)]
-async fn display_players(
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn pool(
     ctx: Context<'_>,
+    #[description = "Sort by, defaults to WN8"] sort: Option<PoolSort>,
+    #[description = "Which players to show, defaults to unpicked"] status: Option<PoolStatus>,
+    #[description = "Only players with this tag"] tag: Option<String>,
 ) -> Result<(), Error> {
-    
-    let players = ctx.data().players.lock().await;
+    let sort = sort.unwrap_or(PoolSort::Wn8);
+    let status = status.unwrap_or(PoolStatus::Unpicked);
+    let players = ctx.data().draft.players.lock().await.clone();
+    let mut rows = Vec::new();
     for player in players.iter() {
         let p = player.lock().await;
-        ctx.say(&p.name).await?;
+        let shown = match status {
+            PoolStatus::Unpicked => !p.picked,
+            PoolStatus::Picked => p.picked,
+            PoolStatus::All => true,
+        };
+        if !shown || tag.as_ref().is_some_and(|t| !p.has_tag(t)) {
+            continue;
+        }
+        let mut line = p.team_line();
+        if !p.tags.is_empty() {
+            line += &format!(" [{}]", p.tags.join(", "));
+        }
+        let (name, wn8) = (p.name.to_lowercase(), p.recent_wn8);
+        let team = p.team.clone();
+        let price = p.purchase.map(|purchase| purchase.price);
+        // captains lock before their players, let go of the player first
+        drop(p);
+        if let Some(captain) = team {
+            line += &format!(" — {}", captain.lock().await.name);
+            if let Some(price) = price {
+                line += &format!(" ${}", price);
+            }
+        }
+        rows.push((name, wn8, line));
+    }
+    match sort {
+        PoolSort::Name => rows.sort_by(|a, b| a.0.cmp(&b.0)),
+        PoolSort::Wn8 => rows.sort_by_key(|r| std::cmp::Reverse(r.1)),
+    }
+    let title = format!("Player Pool ({} players)", rows.len());
+    let mut pages: Vec<String> = rows.chunks(POOL_PAGE_SIZE)
+        .map(|chunk| chunk.iter().map(|r| r.2.as_str()).collect::<Vec<_>>().join("\n"))
+        .collect();
+    if pages.is_empty() {
+        pages.push(String::from("No players match"));
+    }
+
+    let ctx_id = ctx.id();
+    let prev_id = format!("{}prev", ctx_id);
+    let next_id = format!("{}next", ctx_id);
+    ctx.send(CreateReply::default()
+        .embed(pool_embed(&pages, 0, &title))
+        .components(vec![CreateActionRow::Buttons(vec![
+            CreateButton::new(&prev_id).emoji('◀'),
+            CreateButton::new(&next_id).emoji('▶'),
+        ])])
+        .ephemeral(true))
+        .await?;
+    let mut page = 0;
+    while let Some(press) = serenity::ComponentInteractionCollector::new(ctx)
+        .filter(move |press| press.data.custom_id.starts_with(&ctx_id.to_string()))
+        .timeout(Duration::from_secs(15 * 60))
+        .await
+    {
+        if press.data.custom_id == next_id {
+            page = (page + 1) % pages.len();
+        }
+        else if press.data.custom_id == prev_id {
+            page = page.checked_sub(1).unwrap_or(pages.len() - 1);
+        }
+        else {
+            continue;
+        }
+        press.create_response(ctx, CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new().embed(pool_embed(&pages, page, &title))
+        )).await?;
     }
     Ok(())
 }
 
+/// Adds a captain, give a player, WN8 or value if the captain also plays
 #[poise::command(slash_command, prefix_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
 async fn add_captain(
     ctx: Context<'_>,
     #[description = "Select User"] user: serenity::User,
     #[description = "Captain Name"] name: String,
+    #[description = "Pool player this captain plays as"]
+    #[autocomplete = "autocomplete_player"]
+    player: Option<String>,
+    #[description = "Captain's WN8, priced with the captain cost config"] wn8: Option<u32>,
+    #[description = "Manual budget reduction, overrides the WN8 price"] value: Option<u32>,
 ) -> Result<(), Error> {
-    let u = user;
-    let config = ctx.data().config.lock().await; 
-    let captain = Captain::new(
-        u64::from(u.id),  
-        name.clone(),
-        config.starting_balance,
-    );
-    let mut captains = ctx.data().captains.lock().await;
-    for captain in captains.to_vec() {
-        if captain.lock().await.discord_id == u64::from(u.id) {
-
-            let _ = ctx.send(CreateReply::default()
-                .content(format!("Already Added this captain"))
-                .reply(true)
-                .ephemeral(false))
-                .await;
-            return Ok(())
-        }
-    }
-    captains.push(Arc::new(Mutex::new(captain)));
-    let _ = ctx.send(CreateReply::default()
-        .content(format!("Added captain {}", name))
-        .reply(true)
-        .ephemeral(false))
-        .await;
-    Ok(())
-
+    let config = ctx.data().config.lock().await.clone();
+    commands::add_captain(
+        &ctx.data().draft,
+        &config,
+        u64::from(user.id),
+        &name,
+        player.as_deref(),
+        wn8,
+        value,
+        &mut Replier(ctx),
+    ).await
 }
 
 
 #[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
 async fn pick(
     ctx: Context<'_>,
     #[description = "Select a Player"]
@@ -310,440 +354,1485 @@ async fn pick(
     #[description = "Starting Bid"]
     starting_bid: Option<u32>,
 ) -> Result<(), Error> {
-    let mut draft_state = ctx.data().draft_state.lock().await;
-    let current_captain = &draft_state.round_captain;
-    let max_bid;
-    let config = ctx.data().config.lock().await;
-    if draft_state.time != config.round_time {
-        let _ = ctx.send(CreateReply::default()
-            .content("Only pick before round starts")
-            .reply(true)
-            .ephemeral(true))
-            .await;
-        return Ok(())
-    }
-    let mut final_bid = config.min_bid;
-    drop(config);
-    match current_captain {
-        Some(captain) => {
-            let c1 = captain.clone();
-            let c = c1.lock().await;
-            if ctx.author().id != c.discord_id {
-                let _ = ctx.send(CreateReply::default()
-                    .content("You not da captain blud")
-                    .reply(true)
-                    .ephemeral(true))
-                    .await;
-                return Ok(());
-            }
-            let config = ctx.data().config.lock().await;
-            max_bid = c.get_max_bid(&config);
-            draft_state.current_winner = Some(captain.clone());
-        }
-        None => {
-            let _ = ctx.send(CreateReply::default()
-                .content("No Draft Running")
-                .reply(true)
-                .ephemeral(true))
-                .await;
-            return Ok(())
-        }
-
-    }
+    let session = ctx.data().session().await;
+    let config = ctx.data().config.lock().await.clone();
+    commands::pick(&session, &config, u64::from(ctx.author().id), &player, starting_bid, &mut Replier(ctx)).await
+}
 
-    let message = ctx.reply("Pick Processed").await?;
-    message.delete(ctx).await?;
-    drop(draft_state);
-    let players = ctx.data().players.lock().await;
+/// Links `user_id` to the pool player `player`, the reply text either way
+async fn link_player(data: &UserData, player: &str, user_id: u64) -> Result<String, String> {
+    let players = data.draft.players.lock().await;
+    let mut found = None;
     for p_lock in players.iter() {
         let p = p_lock.lock().await;
-        if p.name.eq(&player) && !p.picked {
-            let mut draft_state = ctx.data().draft_state.lock().await;
-            if let Some(bid) = starting_bid {
-                if bid > max_bid {
-                    let _ = ctx.reply("no");
-                    let _ = ctx.send(CreateReply::default()
-                        .content(format!(
-                                "Not enough funds, Your max bid is:{}"
-                                ,max_bid))
-                        .reply(true)
-                        .ephemeral(true))
-                        .await;
-                    return Ok(());
-                }
-                final_bid = bid;
-                
-            }
-                draft_state.nominated_player = Some(p_lock.clone());
-                draft_state.starting_bid = final_bid;
-                draft_state.current_bid = final_bid;
-            
+        if p.discord_id == Some(user_id) && !p.name.eq(player) {
+            return Err(format!("<@{}> is already signed up as {}", user_id, p.name))
+        }
+        if p.name.eq(player) {
+            found = Some(p_lock.clone());
         }
     }
-
-
-
-    Ok(())
-}
-
-pub fn import_players() -> Vec<Arc<Mutex<Player>>> {
-    let mut rdr = csv::Reader::from_path(
-        "/home/zray/code/auction2025/src/players.csv"
-    ).unwrap();
-    let mut players: Vec<Arc<Mutex<Player>>> = Vec::new();
-    for result in rdr.records() {
-        let record = result.unwrap();
-        let name = record.get(0).unwrap();
-        let player = Player::new(
-            name.to_string(),
-            false,
-        );
-        players.push(Arc::new(Mutex::new(player)));
-
+    let Some(p_lock) = found else {
+        return Err(format!("No player named {}", player))
+    };
+    let mut p = p_lock.lock().await;
+    if let Some(id) = p.discord_id {
+        if id != user_id {
+            return Err(format!("{} is already linked to another account", p.name))
+        }
     }
-    return players;
+    p.discord_id = Some(user_id);
+    Ok(format!("Linked {} to <@{}>", p.name, user_id))
 }
 
-pub async fn autocomplete_player<'a>(
-    ctx: Context<'a>,
-    partial: &'a str,
-) -> impl Iterator<Item = String> + 'a {
-    let players = ctx.data().players.lock().await;
-    let mut player_strs = Vec::new();
-
-    for player in players.iter() {
-        let p1 = player.lock().await;
-        if p1.name.to_lowercase().starts_with(&partial.to_lowercase()) && !p1.picked {
-            player_strs.push(p1.name.clone());
-        }
-
+/// True when `player` is the author's username, display name or server
+/// nickname. Anyone else has to be linked by an admin
+async fn is_own_name(ctx: Context<'_>, player: &str) -> bool {
+    let author = ctx.author();
+    let mut names = vec![Some(author.name.clone()), author.global_name.clone()];
+    if let Some(member) = ctx.author_member().await {
+        names.push(member.nick.clone());
     }
-    return player_strs.into_iter();
-
+    names.into_iter().flatten().any(|name| name.eq_ignore_ascii_case(player))
 }
 
+/// Links your Discord account to the pool player with your Discord name
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn signup(
+    ctx: Context<'_>,
+    #[description = "Select your Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+) -> Result<(), Error> {
+    let content = if is_own_name(ctx, &player).await {
+        link_player(ctx.data(), &player, u64::from(ctx.author().id)).await
+            .unwrap_or_else(|e| e)
+    } else {
+        format!("{} doesn't match your Discord name, ask an admin to /link you", player)
+    };
+    let _ = ctx.send(CreateReply::default()
+        .content(content)
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
 
-pub fn get_wn8_color(wn8: u32) -> i32 {
-    match wn8 {
-        0 => 0x808080,
-        1..=300 => 0x930D0D,
-        301..=450 => 0xCD3333,
-        451..=650 => 0xCC7A00,
-        651..=900 => 0xCCB800,
-        901..=1200 => 0x849B24,
-        1201..=1600 => 0x4D7326,
-        1601..=2000 => 0x4099BF,
-        2001..=2450 => 0x3972C6,
-        2451..=2900 => 0x6844d4,
-        2901..=3400 => 0x522b99,
-        3401..=4000 => 0x411d73,
-        4001..=4700 => 0x310d59,
-        4701..=u32::MAX => 0x24073d,
-    }
+/// Links a Discord account to a pool player whose name doesn't match
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn link(
+    ctx: Context<'_>,
+    #[description = "Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+    #[description = "Their Discord account"] user: serenity::User,
+) -> Result<(), Error> {
+    let content = link_player(ctx.data(), &player, u64::from(user.id)).await
+        .unwrap_or_else(|e| e);
+    let _ = ctx.send(CreateReply::default()
+        .content(content)
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
 }
-pub async fn make_final_draft_embed(captains: Vec<Arc<Mutex<Captain>>>) -> CreateEmbed {
-    let mut embed = CreateEmbed::default().title("Draft");
-    for captain in captains {
-        let c1 = captain.lock().await;
-        let mut player_str = String::from("");
-        for player in &c1.players {
-            let p1 = player.lock().await;
-            player_str.push_str(&p1.name);
-            player_str.push_str("\n");
+
+/// Shows the team you were drafted onto
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn my_team(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let author_id = u64::from(ctx.author().id);
+    let players = ctx.data().draft.players.lock().await;
+    let mut team = None;
+    let mut linked = false;
+    for p_lock in players.iter() {
+        let p = p_lock.lock().await;
+        if p.discord_id == Some(author_id) {
+            linked = true;
+            team = p.team.clone();
+            break;
         }
-        embed = embed.field(
-            c1.name.clone(),
-            player_str,
-            true
-        );
-    }
-    return embed;
-}
-
-pub async fn generate_draft_embed(draft_state: &DraftState) -> CreateEmbed {
-    let mut embed = CreateEmbed::default().title("Draft");
-    if let Some(captain) = &draft_state.round_captain {
-        let name = &captain.lock().await.name;
-        embed = embed.field(
-            "Round Info",
-            format!("Round: `{}`\nCaptain: '{}'\nTime Left: {}",
-                draft_state.current_round,
-                name,
-                draft_state.time,
-                ),
-                true
-        );
     }
-    else {
-        
-        embed = embed.field(
-            "Round Info",
-            format!("Round: `{}`\nCaptain: '{}'",
-                draft_state.current_round,
-                "None",
-                ),
-                true
-        );
+    drop(players);
+    if !linked {
+        let _ = ctx.send(CreateReply::default()
+            .content("You are not linked to a player, use /signup first")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
     }
-    if let Some(player) = &draft_state.nominated_player {
+    let Some(captain) = team else {
+        let _ = ctx.send(CreateReply::default()
+            .content("You have not been drafted yet")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let c1 = captain.lock().await;
+    let mut player_str = String::new();
+    for player in &c1.players {
         let p1 = player.lock().await;
-        embed = embed.field(
-            "Player info",
-            format!("Name: `{}`\nRecent wn8: `{}`",
-                p1.name,
-                p1.recent_wn8,
-            ),
-            true
-
-        );
-    }
-    else {
-        embed = embed.field(
-            "Player info",
-            format!("Name: `{}`\nRecent wn8: `{}`\nInfo: '{}'",
-                "None",
-                "0",
-                "None",
-            ),
-            true
-
-        );
-    }
-    if let Some(captain) = &draft_state.current_winner {
-        let c1 = captain.lock().await;
-        embed = embed.field(
-            "Bid Info",
-            format!("Starting Bid: `{}`\nCurrent Winner: `{}`\nCurrent Bid: `{}`",
-                draft_state.starting_bid,
-                c1.name,
-                draft_state.current_bid,
-
-            ),
-            true
-        );
-    }
-    else {
-        embed = embed.field(
-            "Bid Info",
-            format!("Starting Bid: `{}`\nCurrent Winner: `{}`\nCurrent Bid: `{}`",
-                draft_state.starting_bid,
-                "None",
-                draft_state.current_bid,
-
-            ),
-            true
-        );
-
+        player_str.push_str(&p1.team_line());
+        if p1.discord_id.is_some() {
+            player_str.push(' ');
+            player_str.push_str(&p1.mention());
+        }
+        player_str.push('\n');
     }
-    embed
-    
+    let embed = CreateEmbed::default()
+        .title(format!("Team {}", c1.name))
+        .field("Captain", format!("<@{}>", c1.discord_id), false)
+        .field("Players", player_str, false);
+    let _ = ctx.send(CreateReply::default()
+        .embed(embed)
+        .ephemeral(true))
+        .await;
+    Ok(())
 }
 
-#[poise::command(slash_command)]
-async fn bid(
+/// Sets the note shown on a player's card during the draft
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn set_note(
     ctx: Context<'_>,
-    #[description = "Amount"] amount: u32,
+    #[description = "Select a Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+    #[description = "Note, leave empty to clear"] note: Option<String>,
 ) -> Result<(), Error> {
-    //check if time left, if greater than current max increase.
-    let mut draft_state = ctx.data().draft_state.lock().await;
-    if draft_state.time > 0 {
-        if amount > draft_state.current_bid {
-            let captains = ctx.data().captains.lock().await;
-            let mut captain: Option<Arc<Mutex<Captain>>> = None;
-            let mut max_bid = 0;
-            let mut found = false;
-            for c_lock in captains.iter() {
-                let c = c_lock.lock().await;
-                if c.discord_id == u64::from(ctx.author().id) {
-                    captain = Some(c_lock.clone());
-                    let config = ctx.data().config.lock().await;
-                    max_bid = c.get_max_bid(&config);
-                    drop(config);
-                    found = true;
-                    break;
-                } 
-            }
-            if !found {
-                let _ = ctx.send(CreateReply::default()
-                    .content("Not in captain list")
-                    .reply(true)
-                    .ephemeral(true))
-                    .await;
-                return Ok(())
-            }
-            if amount > max_bid {
-
-                ctx.send(CreateReply::default()
-                    .content(format!("Not enough funds, Your max bid is:{}",max_bid))
-                    .reply(true)
-                    .ephemeral(true))
-                    .await?;
-
-                return Ok(())
-            }
-            draft_state.current_bid = amount;
-            draft_state.current_winner = Some(captain.unwrap()); 
-            draft_state.bid_placed = true;
-            let msg = ctx.send(CreateReply::default()
-                .content(format!("You Bid ${} for {}",amount,draft_state.nominated_player.as_ref().unwrap().lock().await.name))
-                .reply(true)
-                .ephemeral(true))
-                .await?;
-            drop(draft_state);
-            msg.delete(ctx).await?;
-            return Ok(())
-            
-        }
-        else {
-            ctx.send(CreateReply::default()
-                .content("Under current top bid")
+    let players = ctx.data().draft.players.lock().await;
+    for p_lock in players.iter() {
+        let mut p = p_lock.lock().await;
+        if p.name.eq(&player) {
+            p.notes = note.filter(|n| !n.is_empty());
+            let _ = ctx.send(CreateReply::default()
+                .content(p.card())
                 .reply(true)
                 .ephemeral(true))
-                .await?;
+                .await;
             return Ok(())
         }
     }
-    else {
+    let _ = ctx.send(CreateReply::default()
+        .content(format!("No player named {}", player))
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+#[poise::command(slash_command, subcommands("tier_set", "tier_remove", "tier_assign"))]
+async fn tier(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add or update a player tier
+#[poise::command(slash_command, rename = "set", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn tier_set(
+    ctx: Context<'_>,
+    #[description = "Tier name"] name: String,
+    #[description = "Minimum price for players in this tier"] min_price: u32,
+    #[description = "Fewest players of this tier per team"] min_count: Option<u32>,
+    #[description = "Most players of this tier per team"] max_count: Option<u32>,
+) -> Result<(), Error> {
+    let mut config = ctx.data().config.lock().await;
+    let tier = Tier {
+        name: name.clone(),
+        min_price,
+        min_count: min_count.unwrap_or(0),
+        max_count: max_count.unwrap_or(config.team_size),
+    };
+    if tier.min_count > tier.max_count {
         let _ = ctx.send(CreateReply::default()
-            .content("No Auction Running")
+            .content("Minimum count is above the maximum")
             .reply(true)
             .ephemeral(true))
             .await;
         return Ok(())
-
     }
-
+    if tier.min_price > config.starting_balance {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("Minimum price is above the starting balance of {}", config.starting_balance))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let other_minimums: u32 = config.tiers.iter()
+        .filter(|t| !t.name.eq_ignore_ascii_case(&name))
+        .map(|t| t.min_count)
+        .sum();
+    if other_minimums + tier.min_count > config.team_size {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("Tier minimums would add up to {}, more than the team size of {}",
+                other_minimums + tier.min_count,
+                config.team_size,
+            ))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    match config.tiers.iter_mut().find(|t| t.name.eq_ignore_ascii_case(&name)) {
+        Some(existing) => *existing = tier,
+        None => config.tiers.push(tier),
+    }
+    let config_now = config.clone();
+    drop(config);
+    // players imported with another spelling of this tier start counting now
+    let players = ctx.data().draft.players.lock().await.clone();
+    normalize_tiers(&config_now, &players).await;
+    for captain in ctx.data().draft.captains.lock().await.iter() {
+        captain.lock().await.recount().await;
+    }
+    let _ = ctx.say(config_now.to_string()).await;
+    Ok(())
 }
 
+/// Remove a player tier
+#[poise::command(slash_command, rename = "remove", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn tier_remove(
+    ctx: Context<'_>,
+    #[description = "Tier name"] name: String,
+) -> Result<(), Error> {
+    let mut config = ctx.data().config.lock().await;
+    config.tiers.retain(|t| !t.name.eq_ignore_ascii_case(&name));
+    let _ = ctx.say(config.to_string()).await;
+    Ok(())
+}
+
+/// Put a player in a tier
+#[poise::command(slash_command, rename = "assign", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn tier_assign(
+    ctx: Context<'_>,
+    #[description = "Select a Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+    #[description = "Tier name, leave empty to clear"] tier: Option<String>,
+) -> Result<(), Error> {
+    let tier = match tier.filter(|t| !t.is_empty()) {
+        Some(tier) => match ctx.data().config.lock().await.tier(Some(&tier)) {
+            Some(t) => Some(t.name.clone()),
+            None => {
+                let _ = ctx.send(CreateReply::default()
+                    .content(format!("No tier named {}", tier))
+                    .reply(true)
+                    .ephemeral(true))
+                    .await;
+                return Ok(())
+            }
+        },
+        None => None,
+    };
+    for p_lock in ctx.data().draft.players.lock().await.iter() {
+        let mut p = p_lock.lock().await;
+        if !p.name.eq(&player) {
+            continue;
+        }
+        if p.picked {
+            let _ = ctx.send(CreateReply::default()
+                .content(format!("{} is already on a team", p.name))
+                .reply(true)
+                .ephemeral(true))
+                .await;
+            return Ok(())
+        }
+        p.tier = tier;
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("{} tier: {}", p.name, p.tier.as_deref().unwrap_or("None")))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let _ = ctx.send(CreateReply::default()
+        .content(format!("No player named {}", player))
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+#[poise::command(slash_command, subcommands("constraint_add", "constraint_remove", "constraint_list"))]
+async fn constraint(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a roster constraint
+#[poise::command(slash_command, rename = "add", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn constraint_add(
+    ctx: Context<'_>,
+    #[description = "max_tag:<tag>:<n>, min_tag:<tag>:<n>, max_wn8:<cap>, max_wn8:avg:<cap> or max_clan:<n>"] rule: String,
+) -> Result<(), Error> {
+    let constraint: Constraint = match rule.parse() {
+        Ok(constraint) => constraint,
+        Err(e) => {
+            let _ = ctx.send(CreateReply::default()
+                .content(e)
+                .reply(true)
+                .ephemeral(true))
+                .await;
+            return Ok(())
+        }
+    };
+    let mut config = ctx.data().config.lock().await;
+    if !config.constraints.contains(&constraint) {
+        config.constraints.push(constraint);
+    }
+    let _ = ctx.say(config.to_string()).await;
+    Ok(())
+}
+
+/// Remove a roster constraint
+#[poise::command(slash_command, rename = "remove", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn constraint_remove(
+    ctx: Context<'_>,
+    #[description = "Constraint as shown in /constraint list"] rule: String,
+) -> Result<(), Error> {
+    let mut config = ctx.data().config.lock().await;
+    let before = config.constraints.len();
+    config.constraints.retain(|c| c.to_string() != rule.trim().to_lowercase());
+    if config.constraints.len() == before {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("No constraint {}", rule))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let _ = ctx.say(config.to_string()).await;
+    Ok(())
+}
+
+/// List roster constraints
+#[poise::command(slash_command, rename = "list")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn constraint_list(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let config = ctx.data().config.lock().await;
+    let mut content: Vec<String> = config.constraints.iter().map(|c| c.to_string()).collect();
+    if content.is_empty() {
+        content.push(String::from("No constraints"));
+    }
+    let _ = ctx.send(CreateReply::default()
+        .content(content.join("\n"))
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+/// Refreshes player WN8 from the Wargaming API
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn refresh_stats(
+    ctx: Context<'_>,
+    #[description = "Server region, defaults to WG_REGION or EU"] region: Option<wargaming::Region>,
+) -> Result<(), Error> {
+    let _ = ctx.defer().await;
+    let draft_state = ctx.data().draft.draft_state.lock().await;
+    let running = draft_state.draft_started && !draft_state.draft_finished;
+    drop(draft_state);
+    // purchases were checked against the WN8 they had when bought
+    if running {
+        let _ = ctx.send(CreateReply::default()
+            .content("The draft is running, refresh stats before or after it")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let Ok(application_id) = std::env::var("WG_APPLICATION_ID") else {
+        let _ = ctx.send(CreateReply::default()
+            .content("WG_APPLICATION_ID is not set")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let expected = match wn8::ExpectedValues::load(EXPECTED_VALUES_PATH) {
+        Ok(expected) => expected,
+        Err(e) => {
+            let _ = ctx.send(CreateReply::default()
+                .content(format!("Could not load expected values: {}", e))
+                .reply(true)
+                .ephemeral(true))
+                .await;
+            return Ok(())
+        }
+    };
+    let region = region
+        .or_else(|| std::env::var("WG_REGION").ok().and_then(|r| wargaming::Region::from_name(&r)))
+        .unwrap_or(wargaming::Region::Eu);
+    let mut client = wargaming::WargamingClient::new(application_id, region)
+        .with_cache_dir(WG_CACHE_DIR);
+    if let Ok(url) = std::env::var("WG_API_URL") {
+        client = client.with_base_url(url);
+    }
+
+    let players = ctx.data().draft.players.lock().await.clone();
+    let mut names = Vec::new();
+    for player in players.iter() {
+        names.push(player.lock().await.name.clone());
+    }
+    let account_ids = client.account_ids(&names).await?;
+    let ids: Vec<u64> = account_ids.values().copied().collect();
+    let stats = client.player_stats(&ids, RECENT_DAYS).await?;
+
+    let mut updated = 0;
+    let mut missing = Vec::new();
+    let mut no_history = Vec::new();
+    let mut inactive = Vec::new();
+    for player in players.iter() {
+        let mut p = player.lock().await;
+        match account_ids.get(&p.name).and_then(|id| stats.get(id)) {
+            Some(s) => {
+                p.overall_wn8 = expected.calculate(&s.overall).round() as u32;
+                if s.recent_unavailable {
+                    no_history.push(p.name.clone());
+                }
+                // no battles in the window would make the player free under a WN8 cap
+                if s.recent.is_empty() {
+                    p.recent_wn8 = p.overall_wn8;
+                    if !s.recent_unavailable {
+                        inactive.push(p.name.clone());
+                    }
+                } else {
+                    p.recent_wn8 = expected.calculate(&s.recent).round() as u32;
+                }
+                updated += 1;
+            }
+            None => missing.push(p.name.clone()),
+        }
+    }
+    for captain in ctx.data().draft.captains.lock().await.iter() {
+        captain.lock().await.recount().await;
+    }
+    let mut content = format!("Updated stats for {} players", updated);
+    if !missing.is_empty() {
+        content += &format!("\nNo stats found for: {}", missing.join(", "));
+    }
+    if !no_history.is_empty() {
+        content += &format!("\nRecent WN8 unavailable, no stats from {} days ago yet, using overall for: {}",
+            RECENT_DAYS,
+            no_history.join(", "),
+        );
+    }
+    if !inactive.is_empty() {
+        content += &format!("\nNo battles in the last {} days, using overall for: {}",
+            RECENT_DAYS,
+            inactive.join(", "),
+        );
+    }
+    let _ = ctx.say(content).await;
+    Ok(())
+}
+
+pub async fn autocomplete_player<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let session = ctx.data().session().await;
+    let players = session.players.lock().await;
+    let mut player_strs = Vec::new();
+
+    for player in players.iter() {
+        let p1 = player.lock().await;
+        if p1.name.to_lowercase().starts_with(&partial.to_lowercase()) && !p1.picked {
+            player_strs.push(p1.name.clone());
+        }
+
+    }
+    player_strs.into_iter()
+
+}
 
 
 #[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn bid(
+    ctx: Context<'_>,
+    #[description = "Amount"] amount: u32,
+) -> Result<(), Error> {
+    let session = ctx.data().session().await;
+    let config = ctx.data().config.lock().await.clone();
+    commands::bid(&session, &config, u64::from(ctx.author().id), amount, &mut Replier(ctx)).await
+}
+
+
+
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
 async fn start_draft(
     ctx: Context<'_>,
 ) -> Result<(), Error> {
     let _ = ctx.defer().await;
-    let mut captains_main = ctx.data().captains.lock().await;
-    captains_main.shuffle(&mut thread_rng());
-    let config = ctx.data().config.lock().await;
-    let team_size = config.team_size;
-    let captains = captains_main.clone();
-    drop(captains_main);
-    let mut draft_state = ctx.data().draft_state.lock().await;
-    let mut embed = generate_draft_embed(&draft_state).await;
+    if ctx.data().mock.lock().await.is_some() {
+        return Err(DraftError::MockRunning.into());
+    }
+    let mut draft_state = ctx.data().draft.draft_state.lock().await;
+    if draft_state.draft_started {
+        return Err(DraftError::DraftRunning.into());
+    }
+    // claimed here so a second start can't slip in before the auction does
     draft_state.draft_started = true;
     drop(draft_state);
-    drop(config);
-    let mut message = ctx.send(CreateReply::default().embed(embed)).await?.into_message().await?;
-    let mut message2 = ctx.send(CreateReply::default().content("Draft Started")).await?.into_message().await?;
-    for i in 1..team_size+1 {
-        for captain in captains.iter() {
-            let mut draft_state = ctx.data().draft_state.lock().await;
-            let config = ctx.data().config.lock().await;
-            draft_state.current_round = i;
-            draft_state.nominated_player = None;
-            draft_state.starting_bid = config.min_bid;
-            draft_state.current_bid = config.min_bid;
-            draft_state.round_captain = Some(captain.clone());
-            draft_state.current_winner = None;
-            draft_state.time = config.round_time;
-            embed = generate_draft_embed(&draft_state).await;
-            message.edit(ctx,EditMessage::default().embed(embed)).await?;
-            drop(draft_state);
-            drop(config);
-            let mut interval = time::interval(Duration::from_secs(2));
-            loop {
-                interval.tick().await;
-                let mut draft_state = ctx.data().draft_state.lock().await;
-                let config = ctx.data().config.lock().await;
-                if config.round_time != draft_state.time {
-                    draft_state.time = config.round_time;
-                    embed = generate_draft_embed(&draft_state).await;
-                    message.edit(ctx,EditMessage::default().embed(embed)).await?;
-                }
-                if draft_state.nominated_player.is_some() {break;}
+    let mut output = run_auction(ctx, &ctx.data().draft).await;
+    let free_agency_time = ctx.data().config.lock().await.free_agency_time;
+    if free_agency_time > 0 {
+        run_free_agency(ctx, &mut output, free_agency_time).await;
+        let captains = ctx.data().draft.captains.lock().await.clone();
+        // failures were already logged and reported by the output
+        let _ = output.board(final_draft_board(captains).await).await;
+    }
+    ctx.data().draft.draft_state.lock().await.draft_finished = true;
+
+    Ok(())
+}
+
+/// Tries per draft message update before it counts as failed
+const SEND_ATTEMPTS: u32 = 4;
+/// Wait after the first failed try, doubled after every further one
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// What one of the draft messages shows
+enum Update {
+    Board(Board),
+    Status(String),
+}
+
+impl Update {
+    fn edit(&self) -> EditMessage {
+        match self {
+            Update::Board(board) => EditMessage::default().embed(board.embed()),
+            Update::Status(text) => EditMessage::default().content(text),
+        }
+    }
+
+    fn reply(&self) -> CreateReply {
+        match self {
+            Update::Board(board) => CreateReply::default().embed(board.embed()),
+            Update::Status(text) => CreateReply::default().content(text),
+        }
+    }
+
+    fn message(&self) -> CreateMessage {
+        match self {
+            Update::Board(board) => CreateMessage::new().embed(board.embed()),
+            Update::Status(text) => CreateMessage::new().content(text),
+        }
+    }
+}
+
+/// Command replies go back to the interaction that asked
+struct Replier<'a>(Context<'a>);
+
+impl CommandOutput for Replier<'_> {
+    async fn reply(&mut self, text: String, private: bool) -> Result<(), Error> {
+        self.0.send(CreateReply::default()
+            .content(text)
+            .reply(true)
+            .ephemeral(private))
+            .await?;
+        Ok(())
+    }
+}
+
+/// True when Discord no longer has the message, someone deleted it
+fn message_gone(error: &serenity::Error) -> bool {
+    matches!(error, serenity::Error::Http(e) if e.status_code().map(|s| s.as_u16()) == Some(404))
+}
+
+/// Draft embed and status message in the channel the draft was started in
+struct DiscordOutput<'a> {
+    ctx: Context<'a>,
+    mock: bool,
+    board: Option<serenity::Message>,
+    status: Option<serenity::Message>,
+    /// The draft command got its reply, new messages go to the channel
+    replied: bool,
+    /// Admins were told updates are failing, cleared once one gets through
+    alerted: bool,
+}
+
+impl DiscordOutput<'_> {
+    /// Shows `update` in `message`, retrying with backoff. The first message
+    /// answers the command, a deleted one is posted again in the channel
+    async fn deliver(&mut self, update: Update, board: bool) -> Result<(), serenity::Error> {
+        let ctx = self.ctx;
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let slot = if board { &mut self.board } else { &mut self.status };
+            let result = match slot {
+                Some(message) => match message.edit(ctx, update.edit()).await {
+                    Err(e) if message_gone(&e) => {
+                        warn!(message = %message.id, "draft message was deleted, posting it again");
+                        *slot = None;
+                        continue;
+                    }
+                    result => result,
+                },
+                None if !self.replied => match ctx.send(update.reply()).await {
+                    Ok(reply) => reply.into_message().await.map(|message| {
+                        self.replied = true;
+                        *slot = Some(message);
+                    }),
+                    Err(e) => Err(e),
+                },
+                None => ctx.channel_id().send_message(ctx, update.message()).await
+                    .map(|message| *slot = Some(message)),
+            };
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= SEND_ATTEMPTS => return Err(e),
+                Err(e) => warn!(attempt, error = %e, "draft update failed, retrying"),
             }
-            let config = ctx.data().config.lock().await;
-            let draft_state = ctx.data().draft_state.lock().await;
-            embed = generate_draft_embed(&draft_state).await;
-            message2.edit(ctx,EditMessage::default().content(
-                    format!("{} bid {} for {}", 
-                        draft_state.current_winner.as_ref().unwrap().lock().await.name,
-                        draft_state.current_bid,
-                        draft_state.nominated_player.as_ref().unwrap().lock().await.name,
-
-                    )
-            )).await?;
-            drop(draft_state);
-            message.edit(ctx,EditMessage::default().embed(embed)).await?;
-            let mut time_left: u32 = config.round_time;
-            drop(config);
-            while time_left > 0 {
-                interval.tick().await;
-                let mut draft_state = ctx.data().draft_state.lock().await;
-                if time_left % 2 != 0 {
-                    time_left +=1;
-                }
-                time_left = time_left-2;
-                draft_state.time = time_left;
-                embed = generate_draft_embed(&draft_state).await;
-                message.edit(ctx,EditMessage::default().embed(embed)).await?;
-                if draft_state.bid_placed == true {
-                    let config = ctx.data().config.lock().await;
-                    time_left = time_left + config.bid_add_time;
-                    draft_state.bid_placed = false;
-                    message2.edit(ctx,EditMessage::default()
-                        .content(
-                            format!("{} bid {} for {}", 
-                                draft_state.current_winner.as_ref().unwrap().lock().await.name,
-                                draft_state.current_bid,
-                                draft_state.nominated_player.as_ref().unwrap().lock().await.name,
-
-                            )
-                        )
-                    ).await?;
-                }
+            time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Tells the admin who started the draft once per outage, the auction
+    /// keeps going without its messages
+    async fn report(&mut self, result: Result<(), serenity::Error>) -> Result<(), Error> {
+        let Err(e) = result else {
+            if self.alerted {
+                info!("draft messages are updating again");
+                self.alerted = false;
             }
-            let draft_state = ctx.data().draft_state.lock().await;
-            let mut winner = draft_state.current_winner.as_ref().unwrap().lock().await;
-            winner.balance = winner.balance - draft_state.current_bid;
-            drop(winner);
-            add_player_to_captain(
-                draft_state.current_winner.as_ref().unwrap().clone(),
-                draft_state.nominated_player.as_ref().unwrap().clone(),
-            ).await;
-            message2.edit(ctx,EditMessage::default().content(format!("{} bought {} for ${}",
-                        draft_state.current_winner.as_ref().unwrap().lock().await.name,
-                        draft_state.nominated_player.as_ref().unwrap().lock().await.name,
-                        draft_state.current_bid,
-            ))).await?;
+            return Ok(())
+        };
+        error!(error = %e, "draft update failed after {} tries", SEND_ATTEMPTS);
+        if !self.alerted {
+            self.alerted = true;
+            let _ = self.ctx.author().direct_message(self.ctx, CreateMessage::new().content(format!(
+                "The draft in <#{}> can't update its messages ({}). The auction is still running, /display_captains shows the teams",
+                self.ctx.channel_id(),
+                e,
+            ))).await;
+        }
+        Err(e.into())
+    }
+}
+
+impl DraftOutput for DiscordOutput<'_> {
+    async fn board(&mut self, board: Board) -> Result<(), Error> {
+        let result = self.deliver(Update::Board(board), true).await;
+        self.report(result).await
+    }
+
+    async fn status(&mut self, text: String) -> Result<(), Error> {
+        let result = self.deliver(Update::Status(text), false).await;
+        self.report(result).await
+    }
 
+    async fn sold(&mut self, sale: &auction::Sale) -> Result<(), Error> {
+        let ctx = self.ctx;
+        // copied out so no captain or player stays locked while Discord is slow
+        let (winner_name, winner_id) = {
+            let winner = sale.captain.lock().await;
+            (winner.name.clone(), winner.discord_id)
+        };
+        let (player_name, mention, player_id) = {
+            let sold = sale.player.lock().await;
+            (sold.name.clone(), sold.mention(), sold.discord_id)
+        };
+        if !self.mock {
+            log_event(ctx.data(), format!("Round {}: {} bought {} for ${}",
+                sale.round,
+                winner_name,
+                player_name,
+                sale.price,
+            )).await;
+        }
+        // the sale happened either way, announce it even if the status line
+        // could not be updated
+        let delivered = self.status(format!("{} bought {} for ${}{}",
+            winner_name,
+            if self.mock { &player_name } else { &mention },
+            sale.price,
+            if sale.bids == 0 { ", no other bids" } else { "" },
+        )).await;
+        if self.mock {
+            return delivered
+        }
+        if let Some(player_id) = player_id {
+            let _ = ctx.say(format!("{} you were bought by {} (<@{}>) for ${}",
+                    mention,
+                    winner_name,
+                    winner_id,
+                    sale.price,
+            )).await;
+            if ctx.data().config.lock().await.dm_players {
+                let _ = serenity::UserId::new(player_id).direct_message(ctx,
+                    CreateMessage::new().content(format!(
+                        "You were drafted onto team {} (captain <@{}>) for ${}",
+                        winner_name,
+                        winner_id,
+                        sale.price,
+                    ))
+                ).await;
+            }
+        }
+        delivered
+    }
+}
 
+/// Runs every round of `session` and posts the final teams. Discord gets the
+/// updates beside the auction, retries never hold up the lot clock. The
+/// output keeps the draft embed and status messages for whatever comes
+/// after the auction
+async fn run_auction<'a>(
+    ctx: Context<'a>,
+    session: &auction::Session,
+) -> DiscordOutput<'a> {
+    let mut output = DiscordOutput {
+        ctx,
+        mock: session.mock,
+        board: None,
+        status: None,
+        replied: false,
+        alerted: false,
+    };
+    let (mut queue, updates) = runner::output_queue();
+    let auction = async move {
+        runner::run_auction(session, &ctx.data().config, &mut queue, &mut runner::IntervalClock::default()).await;
+        // dropping the queue lets delivery finish once it has caught up
+    };
+    tokio::join!(auction, runner::deliver_queued(updates, &mut output));
+    output
+}
 
+#[poise::command(slash_command, subcommands("mock_draft_start", "mock_draft_result"))]
+async fn mock_draft(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
 
+/// Captain discord ids from comma separated mentions or names
+async fn parse_captains(session: &auction::Session, text: &str) -> Result<Vec<u64>, String> {
+    let mut ids = Vec::new();
+    for entry in text.split(',').map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let mention = entry.trim_start_matches("<@").trim_start_matches('!').trim_end_matches('>');
+        let mut found = None;
+        for captain in session.captains.lock().await.iter() {
+            let c1 = captain.lock().await;
+            if mention.parse() == Ok(c1.discord_id) || c1.name.eq_ignore_ascii_case(entry) {
+                found = Some(c1.discord_id);
+                break;
+            }
+        }
+        match found {
+            Some(id) => ids.push(id),
+            None => return Err(format!("No captain named {}", entry)),
         }
     }
-    let captains = ctx.data().captains.lock().await;
-    embed = make_final_draft_embed(captains.to_vec()).await;
-    message.edit(ctx,EditMessage::default().embed(embed)).await?;
+    Ok(ids)
+}
 
+/// Practice auction against bot captains, nothing carries over to the real draft
+#[poise::command(slash_command, rename = "start", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn mock_draft_start(
+    ctx: Context<'_>,
+    #[description = "Bot strategy, a mix when empty"] strategy: Option<bots::Strategy>,
+    #[description = "Captains who play themselves, comma separated mentions or names"] humans: Option<String>,
+    #[description = "Keep the result for /mock_draft result"] save: Option<bool>,
+) -> Result<(), Error> {
+    let _ = ctx.defer().await;
+    let draft_state = ctx.data().draft.draft_state.lock().await;
+    let real_running = draft_state.draft_started && !draft_state.draft_finished;
+    drop(draft_state);
+    let mut error = None;
+    if real_running {
+        error = Some(String::from("The draft is running"));
+    }
+    else if ctx.data().mock.lock().await.is_some() {
+        error = Some(String::from("A mock draft is already running"));
+    }
+    else if ctx.data().draft.captains.lock().await.is_empty() {
+        error = Some(String::from("Add captains first"));
+    }
+    let humans = match parse_captains(&ctx.data().draft, humans.as_deref().unwrap_or("")).await {
+        Ok(humans) => humans,
+        Err(e) => {
+            error.get_or_insert(e);
+            Vec::new()
+        }
+    };
+    if let Some(e) = error {
+        let _ = ctx.send(CreateReply::default()
+            .content(e)
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let mock = Arc::new(auction::Session::mock_copy(&ctx.data().draft, &humans, strategy, rand::random()).await);
+    *ctx.data().mock.lock().await = Some(mock.clone());
+    let mut output = run_auction(ctx, &mock).await;
+    *ctx.data().mock.lock().await = None;
+    let content = if save.unwrap_or(false) {
+        *ctx.data().mock_result.lock().await = Some(mock);
+        "Mock draft finished, see /mock_draft result"
+    } else {
+        "Mock draft finished, the result was discarded"
+    };
+    let _ = output.status(String::from(content)).await;
     Ok(())
 }
 
+/// Teams from the last saved mock draft
+#[poise::command(slash_command, rename = "result")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn mock_draft_result(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let Some(mock) = ctx.data().mock_result.lock().await.clone() else {
+        let _ = ctx.send(CreateReply::default()
+            .content("No saved mock draft")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let captains = mock.captains.lock().await.clone();
+    let _ = ctx.send(CreateReply::default()
+        .embed(make_final_draft_embed(captains.clone()).await)
+        .embed(report::team_report_embed(&captains).await))
+        .await;
+    Ok(())
+}
+
+/// Opens sealed bidding on the leftover players for `window` seconds
+async fn run_free_agency(
+    ctx: Context<'_>,
+    output: &mut DiscordOutput<'_>,
+    window: u32,
+) {
+    let mut remaining = 0;
+    for player in ctx.data().draft.players.lock().await.iter() {
+        if !player.lock().await.picked {
+            remaining += 1;
+        }
+    }
+    if remaining == 0 {
+        return
+    }
+    let mut fa = ctx.data().free_agency.lock().await;
+    fa.bids.clear();
+    fa.open = true;
+    drop(fa);
+    let mut time_left = window;
+    let mut interval = time::interval(Duration::from_secs(5));
+    // the first tick completes immediately
+    interval.tick().await;
+    while time_left > 0 {
+        let _ = output.status(format!(
+            "Free agency is open for {} players, bid with /fa_bid. Closes in {}s",
+            remaining,
+            time_left,
+        )).await;
+        interval.tick().await;
+        time_left = time_left.saturating_sub(5);
+    }
+    let config = ctx.data().config.lock().await.clone();
+    let round = ctx.data().draft.draft_state.lock().await.current_round + 1;
+    let players = ctx.data().draft.players.lock().await.clone();
+    let signings = ctx.data().free_agency.lock().await.resolve(&config, &players, round).await;
+    let mut results = String::from("Free agency closed");
+    for signing in signings.iter() {
+        results += &format!("\n{} signed {} for ${}", signing.captain, signing.player, signing.amount);
+        log_event(ctx.data(), format!("Free agency: {} signed {} for ${}",
+            signing.captain,
+            signing.player,
+            signing.amount,
+        )).await;
+    }
+    if signings.is_empty() {
+        results += "\nNo players signed";
+    }
+    let _ = output.status(results).await;
+}
+
+pub async fn find_captain(data: &UserData, discord_id: u64) -> Option<Arc<Mutex<Captain>>> {
+    data.draft.find_captain(discord_id).await
+}
+
+/// Finds drafted players by comma separated names, ignoring case
+async fn find_players(data: &UserData, names: &str) -> Result<Vec<Arc<Mutex<Player>>>, String> {
+    let players = data.draft.players.lock().await;
+    let mut found: Vec<Arc<Mutex<Player>>> = Vec::new();
+    for name in names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let mut matched = None;
+        for p_lock in players.iter() {
+            if p_lock.lock().await.name.eq_ignore_ascii_case(name) {
+                matched = Some(p_lock.clone());
+                break;
+            }
+        }
+        match matched {
+            Some(p) if found.iter().any(|f| Arc::ptr_eq(f, &p)) => return Err(format!("{} is listed twice", name)),
+            Some(p) => found.push(p),
+            None => return Err(format!("No player named {}", name)),
+        }
+    }
+    Ok(found)
+}
+
+#[poise::command(
+    slash_command,
+    subcommands("trade_propose", "trade_accept", "trade_reject", "trade_approve", "trade_list"),
+)]
+async fn trade(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Offer players and/or money to another captain
+#[poise::command(slash_command, rename = "propose")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn trade_propose(
+    ctx: Context<'_>,
+    #[description = "Captain to trade with"] captain: serenity::User,
+    #[description = "Your players to give, comma separated"] give: Option<String>,
+    #[description = "Money to give"] give_money: Option<u32>,
+    #[description = "Their players you want, comma separated"] receive: Option<String>,
+    #[description = "Money you want"] receive_money: Option<u32>,
+) -> Result<(), Error> {
+    let draft_state = ctx.data().draft.draft_state.lock().await;
+    if !draft_state.draft_started || !draft_state.draft_finished {
+        let _ = ctx.send(CreateReply::default()
+            .content("Trades open after the draft")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    drop(draft_state);
+    let from = find_captain(ctx.data(), u64::from(ctx.author().id)).await;
+    let to = find_captain(ctx.data(), u64::from(captain.id)).await;
+    let (Some(from), Some(to)) = (from, to) else {
+        let _ = ctx.send(CreateReply::default()
+            .content("Both sides of a trade must be captains")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let give = find_players(ctx.data(), give.as_deref().unwrap_or("")).await;
+    let receive = find_players(ctx.data(), receive.as_deref().unwrap_or("")).await;
+    let (give, receive) = match (give, receive) {
+        (Ok(give), Ok(receive)) => (give, receive),
+        (Err(e), _) | (_, Err(e)) => {
+            let _ = ctx.send(CreateReply::default()
+                .content(e)
+                .reply(true)
+                .ephemeral(true))
+                .await;
+            return Ok(())
+        }
+    };
+    let mut next_id = ctx.data().next_trade_id.lock().await;
+    *next_id += 1;
+    let trade = trade::Trade {
+        id: *next_id,
+        from,
+        to,
+        give,
+        receive,
+        give_money: give_money.unwrap_or(0),
+        receive_money: receive_money.unwrap_or(0),
+        status: trade::TradeStatus::Proposed,
+    };
+    drop(next_id);
+    let config = ctx.data().config.lock().await.clone();
+    let floor = auction::wn8_floor(&ctx.data().draft.players.lock().await, None).await;
+    if let Err(e) = trade.validate(&config, floor).await {
+        let _ = ctx.send(CreateReply::default()
+            .content(e)
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let id = trade.id;
+    let description = trade.describe().await;
+    ctx.data().trades.lock().await.push(trade);
+    let _ = ctx.say(format!("<@{}> {}\nUse `/trade accept {}` or `/trade reject {}`",
+        captain.id,
+        description,
+        id,
+        id,
+    )).await;
+    Ok(())
+}
+
+/// Runs a trade both captains agreed to. `trades` stays locked until the
+/// rosters have changed, so no other trade is checked against stale ones
+async fn complete_trade(
+    ctx: Context<'_>,
+    trades: tokio::sync::MutexGuard<'_, Vec<trade::Trade>>,
+    trade: trade::Trade,
+) -> Result<(), Error> {
+    let config = ctx.data().config.lock().await.clone();
+    let floor = auction::wn8_floor(&ctx.data().draft.players.lock().await, None).await;
+    if let Err(e) = trade.validate(&config, floor).await {
+        let _ = ctx.say(format!("Trade #{} is no longer valid: {}", trade.id, e)).await;
+        return Ok(())
+    }
+    trade.execute().await?;
+    // rosters are settled, other trades can be checked against them now
+    drop(trades);
+    let description = trade.describe().await;
+    log_event(ctx.data(), description.clone()).await;
+    let _ = ctx.say(format!("Completed {}", description)).await;
+    Ok(())
+}
+
+/// Accept a trade offered to you
+#[poise::command(slash_command, rename = "accept")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn trade_accept(
+    ctx: Context<'_>,
+    #[description = "Trade number"] id: u32,
+) -> Result<(), Error> {
+    let captain = find_captain(ctx.data(), u64::from(ctx.author().id)).await;
+    let mut trades = ctx.data().trades.lock().await;
+    let index = trades.iter().position(|t| {
+        t.id == id
+            && t.status == trade::TradeStatus::Proposed
+            && captain.as_ref().is_some_and(|c| Arc::ptr_eq(c, &t.to))
+    });
+    let Some(index) = index else {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("No trade #{} waiting on you", id))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    if ctx.data().config.lock().await.trade_approval {
+        trades[index].status = trade::TradeStatus::AwaitingApproval;
+        let _ = ctx.say(format!("Trade #{} accepted, waiting on an admin to `/trade approve {}`", id, id)).await;
+        return Ok(())
+    }
+    let trade = trades.remove(index);
+    complete_trade(ctx, trades, trade).await
+}
+
+/// Reject a trade offered to you, or cancel one you proposed
+#[poise::command(slash_command, rename = "reject")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn trade_reject(
+    ctx: Context<'_>,
+    #[description = "Trade number"] id: u32,
+) -> Result<(), Error> {
+    let captain = find_captain(ctx.data(), u64::from(ctx.author().id)).await;
+    let mut trades = ctx.data().trades.lock().await;
+    let index = trades.iter().position(|t| {
+        t.id == id && captain.as_ref().is_some_and(|c| t.involves(c))
+    });
+    let Some(index) = index else {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("No trade #{} involving you", id))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    trades.remove(index);
+    let _ = ctx.say(format!("Trade #{} rejected", id)).await;
+    Ok(())
+}
+
+/// Approve or deny an accepted trade
+#[poise::command(slash_command, rename = "approve", required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn trade_approve(
+    ctx: Context<'_>,
+    #[description = "Trade number"] id: u32,
+    #[description = "Approve (default) or deny"] approve: Option<bool>,
+) -> Result<(), Error> {
+    let mut trades = ctx.data().trades.lock().await;
+    let index = trades.iter().position(|t| {
+        t.id == id && t.status == trade::TradeStatus::AwaitingApproval
+    });
+    let Some(index) = index else {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("No trade #{} waiting on approval", id))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let trade = trades.remove(index);
+    if !approve.unwrap_or(true) {
+        drop(trades);
+        let _ = ctx.say(format!("Trade #{} denied", id)).await;
+        return Ok(())
+    }
+    complete_trade(ctx, trades, trade).await
+}
+
+/// List open trades
+#[poise::command(slash_command, rename = "list")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn trade_list(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let trades = ctx.data().trades.lock().await;
+    let mut content = String::new();
+    for trade in trades.iter() {
+        content += &trade.describe().await;
+        if trade.status == trade::TradeStatus::AwaitingApproval {
+            content += " (awaiting approval)";
+        }
+        content.push('\n');
+    }
+    if content.is_empty() {
+        content = String::from("No open trades");
+    }
+    let _ = ctx.send(CreateReply::default()
+        .content(content)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+/// Assign a player to a captain at a fixed cost before the draft
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn keeper(
+    ctx: Context<'_>,
+    #[description = "Captain keeping the player"] captain: serenity::User,
+    #[description = "Select a Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+    #[description = "Cost, defaults to the player's minimum price"] cost: Option<u32>,
+) -> Result<(), Error> {
+    if ctx.data().draft.draft_state.lock().await.draft_started {
+        let _ = ctx.send(CreateReply::default()
+            .content("Keepers must be set before the draft starts")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let Some(c_lock) = find_captain(ctx.data(), u64::from(captain.id)).await else {
+        let _ = ctx.send(CreateReply::default()
+            .content("Not in captain list")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let mut target = None;
+    for p_lock in ctx.data().draft.players.lock().await.iter() {
+        let p = p_lock.lock().await;
+        if p.name.eq(&player) && !p.picked {
+            target = Some(p_lock.clone());
+            break;
+        }
+    }
+    let Some(target) = target else {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("{} is not available", player))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let config = ctx.data().config.lock().await.clone();
+    let floor = auction::wn8_floor(&ctx.data().draft.players.lock().await, Some(&target)).await;
+    let mut c1 = c_lock.lock().await;
+    let p = target.lock().await;
+    let min_price = config.min_price(p.tier.as_deref());
+    let cost = cost.unwrap_or(min_price);
+    // same rules as buying the player in the auction
+    let refusal = if c1.players.len() as u32 >= config.team_size {
+        Some(format!("{}'s roster is full", c1.name))
+    } else if let Err(e) = c1.check_player(&config, &p, floor) {
+        Some(format!("{} can't keep {}: {}", c1.name, player, e))
+    } else if cost < min_price {
+        Some(format!("Minimum price for {} is ${}", player, min_price))
+    } else if cost > c1.get_max_bid(&config, Some(&p)) {
+        Some(format!("{} can't afford {} for ${} and still fill their roster", c1.name, player, cost))
+    } else {
+        None
+    };
+    drop(p);
+    if let Some(refusal) = refusal {
+        let _ = ctx.send(CreateReply::default()
+            .content(refusal)
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    c1.balance -= cost;
+    let name = c1.name.clone();
+    drop(c1);
+    add_keeper(c_lock, target, cost).await;
+    log_event(ctx.data(), format!("Keeper: {} kept {} for ${}", name, player, cost)).await;
+    let _ = ctx.say(format!("{} keeps {} for ${}", name, player, cost)).await;
+    Ok(())
+}
+
+/// Undo a keeper before the draft, refunding the cost
+#[poise::command(slash_command, required_permissions = "MANAGE_GUILD")]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn remove_keeper(
+    ctx: Context<'_>,
+    #[description = "Kept player"] player: String,
+) -> Result<(), Error> {
+    if ctx.data().draft.draft_state.lock().await.draft_started {
+        let _ = ctx.send(CreateReply::default()
+            .content("Keepers can't be changed after the draft starts")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    match auction::release_keeper(&ctx.data().draft, &player).await {
+        Ok((captain, refund)) => {
+            log_event(ctx.data(), format!("Keeper: {} released {}", captain, player)).await;
+            let _ = ctx.say(format!("{} released {}, refunded ${}", captain, player, refund)).await;
+        }
+        Err(_) => {
+            let _ = ctx.send(CreateReply::default()
+                .content(format!("{} is not a keeper", player))
+                .reply(true)
+                .ephemeral(true))
+                .await;
+        }
+    }
+    Ok(())
+}
+
+/// Shows the most recent sales and trades
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn draft_log(
+    ctx: Context<'_>,
+    #[description = "Number of entries, defaults to 20"] count: Option<usize>,
+) -> Result<(), Error> {
+    let log = ctx.data().draft_log.lock().await;
+    let count = count.unwrap_or(20);
+    let mut content = String::new();
+    for entry in log.iter().skip(log.len().saturating_sub(count)) {
+        content += &format!("<t:{}:T> {}\n", entry.at, entry.text);
+    }
+    if content.is_empty() {
+        content = String::from("Nothing logged yet");
+    }
+    let _ = ctx.send(CreateReply::default()
+        .content(content)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+/// Sealed bid on a leftover player during free agency, 0 withdraws the bid
+#[poise::command(slash_command)]
+#[instrument(skip(ctx), fields(guild = ?ctx.guild_id(), user = %ctx.author().name))]
+async fn fa_bid(
+    ctx: Context<'_>,
+    #[description = "Select a Player"]
+    #[autocomplete = "autocomplete_player"]
+    player: String,
+    #[description = "Amount"] amount: u32,
+) -> Result<(), Error> {
+    let mut fa = ctx.data().free_agency.lock().await;
+    if !fa.open {
+        let _ = ctx.send(CreateReply::default()
+            .content("Free agency is not open")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    let Some(captain) = find_captain(ctx.data(), u64::from(ctx.author().id)).await else {
+        let _ = ctx.send(CreateReply::default()
+            .content("Not in captain list")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let mut target = None;
+    for p_lock in ctx.data().draft.players.lock().await.iter() {
+        let p = p_lock.lock().await;
+        if p.name.eq(&player) && !p.picked {
+            target = Some(p_lock.clone());
+            break;
+        }
+    }
+    let Some(target) = target else {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("{} is not a free agent", player))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    };
+    let config = ctx.data().config.lock().await.clone();
+    let floor = auction::wn8_floor(&ctx.data().draft.players.lock().await, Some(&target)).await;
+    let c1 = captain.lock().await;
+    let p1 = target.lock().await;
+    let min_price = config.min_price(p1.tier.as_deref());
+    if amount > 0 && amount < min_price {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("Minimum bid is {}", min_price))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    if amount > c1.balance {
+        let _ = ctx.send(CreateReply::default()
+            .content(format!("Not enough funds, Your balance is:{}", c1.balance))
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    if c1.players.len() as u32 >= config.team_size {
+        let _ = ctx.send(CreateReply::default()
+            .content("Your roster is full")
+            .reply(true)
+            .ephemeral(true))
+            .await;
+        return Ok(())
+    }
+    if amount > 0 {
+        if let Err(e) = c1.check_player(&config, &p1, floor) {
+            let _ = ctx.send(CreateReply::default()
+                .content(e)
+                .reply(true)
+                .ephemeral(true))
+                .await;
+            return Ok(())
+        }
+    }
+    drop(c1);
+    drop(p1);
+    fa.place_bid(captain.clone(), target, amount);
+    let mut bids = String::from("Your free agency bids:");
+    for bid in fa.bids.iter().filter(|b| Arc::ptr_eq(&b.captain, &captain)) {
+        bids += &format!("\n{} ${}", bid.player.lock().await.name, bid.amount);
+    }
+    let _ = ctx.send(CreateReply::default()
+        .content(bids)
+        .reply(true)
+        .ephemeral(true))
+        .await;
+    Ok(())
+}
+
+
+/// Replies ephemerally when a command fails. Draft errors are the user's
+/// mistake and are shown as is, anything else gets a generic reply
+async fn on_error(error: poise::FrameworkError<'_, UserData, Error>) {
+    match error {
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            let command = &ctx.command().qualified_name;
+            let content = match error.downcast_ref::<DraftError>() {
+                Some(e) => {
+                    info!(command = %command, user = %ctx.author().name, reason = %e, "command refused");
+                    e.to_string()
+                }
+                None => {
+                    error!(command = %command, user = %ctx.author().name, error = ?error, "command failed");
+                    String::from("Something went wrong, the error was logged")
+                }
+            };
+            let _ = ctx.send(CreateReply::default()
+                .content(content)
+                .reply(true)
+                .ephemeral(true))
+                .await;
+        }
+        other => {
+            if let Err(e) = poise::builtins::on_error(other).await {
+                warn!("could not report an error: {}", e);
+            }
+        }
+    }
+}
+
+/// Logs to stdout and to a file in `LOG_DIR` that rolls over daily. Levels
+/// come from `RUST_LOG`, e.g. `RUST_LOG=auction2025=debug,serenity=info`.
+/// A log directory that can't be created only costs the file
+fn init_logging() -> Option<tracing_appender::non_blocking::WorkerGuard> {
+    use tracing_appender::rolling::{RollingFileAppender, Rotation};
+    use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+    let dir = std::env::var("LOG_DIR").unwrap_or_else(|_| String::from(DEFAULT_LOG_DIR));
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("draft.log")
+        .build(&dir);
+    let (file, guard, failure) = match appender {
+        Ok(appender) => {
+            let (file, guard) = tracing_appender::non_blocking(appender);
+            (Some(fmt::layer().with_ansi(false).with_writer(file)), Some(guard), None)
+        }
+        Err(e) => (None, None, Some(e)),
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer())
+        .with(file)
+        .init();
+    if let Some(e) = failure {
+        warn!(dir, error = %e, "can't write logs there, logging to stdout only");
+    }
+    guard
+}
 
 #[tokio::main]
 async fn main() {
+    // Dropping the guard stops the file writer, keep it for the whole run
+    let _log_guard = init_logging();
+    let players = match import_players() {
+        Ok(players) => players,
+        Err(e) => {
+            error!("could not read the player pool: {}", e);
+            std::process::exit(1);
+        }
+    };
     let user_data = UserData {
-        captains: Mutex::new(Vec::new()),
-        players: Mutex::new(import_players()),
+        draft: Arc::new(auction::Session::new(players)),
+        mock: Mutex::new(None),
+        mock_result: Mutex::new(None),
         config: Mutex::new(Config::default()),
-        draft_state: Mutex::new(DraftState::new()),
+        free_agency: Mutex::new(free_agency::FreeAgency::default()),
+        trades: Mutex::new(Vec::new()),
+        next_trade_id: Mutex::new(0),
+        draft_log: Mutex::new(Vec::new()),
+    };
+    let Ok(token) = std::env::var("DISCORD_TOKEN") else {
+        error!("missing token, set DISCORD_TOKEN");
+        std::process::exit(1);
     };
-    let token = std::env::var("DISCORD_TOKEN").expect("Missing Token");
     let intents = serenity::GatewayIntents::non_privileged();
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands: vec![
-                display_players(), 
+                pool(),
                 start_draft(), 
                 add_captain(), 
                 pick(),
@@ -751,14 +1840,31 @@ async fn main() {
                 display_captains(),
                 config(),
                 display_teams(),
+                signup(),
+                link(),
+                my_team(),
+                refresh_stats(),
+                set_note(),
+                team_report(),
+                my_captain(),
+                export_teams(),
+                fa_bid(),
+                trade(),
+                draft_log(),
+                keeper(),
+                remove_keeper(),
+                tier(),
+                constraint(),
+                mock_draft(),
             ],
+            on_error: |error| Box::pin(on_error(error)),
             ..Default::default()
         })
     .setup(|ctx, _ready, framework| {
         Box::pin(async move {
             poise::builtins::register_in_guild(ctx, 
                 &framework.options().commands, 
-                1318516792821420062.try_into().unwrap()).await?;
+                1318516792821420062.into()).await?;
             Ok(user_data)
         })
     })
@@ -766,7 +1872,17 @@ async fn main() {
     let client = serenity::ClientBuilder::new(token,intents)
         .framework(framework)
         .await;
-    client.unwrap().start().await.unwrap();
+    let mut client = match client {
+        Ok(client) => client,
+        Err(e) => {
+            error!("could not create the Discord client: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = client.start().await {
+        error!("Discord client stopped: {}", e);
+        std::process::exit(1);
+    }
 }
 
 
@@ -0,0 +1,174 @@
+use poise::serenity_prelude::CreateEmbed;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::Captain;
+
+/// A team whose average WN8 is this far from the mean of all teams is flagged
+const OUTLIER_THRESHOLD: f64 = 0.15;
+
+pub struct TeamSummary {
+    pub name: String,
+    pub average_wn8: f64,
+    pub median_wn8: f64,
+    pub spent: u32,
+    pub balance: u32,
+    /// Tag counts as `tag: n`, sorted by tag
+    pub tags: Vec<String>,
+    pub player_count: usize,
+}
+
+impl TeamSummary {
+    pub async fn new(captain: &Captain) -> Self {
+        let mut wn8s = Vec::new();
+        let mut spent = 0;
+        for player in &captain.players {
+            let p1 = player.lock().await;
+            wn8s.push(p1.recent_wn8);
+            spent += p1.purchase.map(|p| p.price).unwrap_or(0);
+        }
+        Self {
+            name: captain.name.clone(),
+            average_wn8: average(&wn8s),
+            median_wn8: median(&wn8s),
+            spent,
+            balance: captain.balance,
+            tags: captain.counts.tags.iter()
+                .filter(|(_, n)| **n > 0)
+                .map(|(tag, n)| format!("{}: {}", tag, n))
+                .collect(),
+            player_count: wn8s.len(),
+        }
+    }
+}
+
+impl std::fmt::Display for TeamSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Avg WN8: `{:.0}`\nMedian WN8: `{:.0}`\nSpent: `${}`\nBalance: `${}`",
+            self.average_wn8,
+            self.median_wn8,
+            self.spent,
+            self.balance,
+        )?;
+        if !self.tags.is_empty() {
+            write!(f, "\nTags: `{}`", self.tags.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn summarize(captains: &[Arc<Mutex<Captain>>]) -> Vec<TeamSummary> {
+    let mut teams = Vec::new();
+    for captain in captains {
+        teams.push(TeamSummary::new(&*captain.lock().await).await);
+    }
+    teams
+}
+
+pub fn average(wn8s: &[u32]) -> f64 {
+    if wn8s.is_empty() {
+        return 0.0;
+    }
+    wn8s.iter().map(|w| *w as f64).sum::<f64>() / wn8s.len() as f64
+}
+
+pub fn median(wn8s: &[u32]) -> f64 {
+    let mut sorted = wn8s.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 0 => (sorted[mid - 1] as f64 + sorted[mid] as f64) / 2.0,
+        _ => sorted[mid] as f64,
+    }
+}
+
+/// 100 when every team has the same average WN8, dropping with the spread
+/// (coefficient of variation) between teams. Empty teams are ignored
+pub fn balance_score(teams: &[TeamSummary]) -> f64 {
+    let averages: Vec<f64> = teams.iter()
+        .filter(|t| t.player_count > 0)
+        .map(|t| t.average_wn8)
+        .collect();
+    if averages.len() < 2 {
+        return 100.0;
+    }
+    let mean = averages.iter().sum::<f64>() / averages.len() as f64;
+    if mean == 0.0 {
+        return 100.0;
+    }
+    let variance = averages.iter().map(|a| (a - mean).powi(2)).sum::<f64>() / averages.len() as f64;
+    (100.0 * (1.0 - variance.sqrt() / mean)).max(0.0)
+}
+
+/// Teams whose average WN8 is more than `OUTLIER_THRESHOLD` off the mean
+pub fn outliers(teams: &[TeamSummary]) -> Vec<&TeamSummary> {
+    let filled: Vec<&TeamSummary> = teams.iter().filter(|t| t.player_count > 0).collect();
+    if filled.len() < 2 {
+        return Vec::new();
+    }
+    let mean = filled.iter().map(|t| t.average_wn8).sum::<f64>() / filled.len() as f64;
+    if mean == 0.0 {
+        return Vec::new();
+    }
+    filled.into_iter()
+        .filter(|t| ((t.average_wn8 - mean) / mean).abs() > OUTLIER_THRESHOLD)
+        .collect()
+}
+
+pub fn balance_text(teams: &[TeamSummary]) -> String {
+    let mut text = format!("Balance Score: `{:.0}/100`", balance_score(teams));
+    for team in outliers(teams) {
+        text += &format!("\n⚠️ {} is an outlier (avg WN8 `{:.0}`)", team.name, team.average_wn8);
+    }
+    text
+}
+
+pub async fn team_report_embed(captains: &[Arc<Mutex<Captain>>]) -> CreateEmbed {
+    let teams = summarize(captains).await;
+    let mut embed = CreateEmbed::default().title("Team Report");
+    for team in &teams {
+        embed = embed.field(team.name.clone(), team.to_string(), true);
+    }
+    embed.field("Balance", balance_text(&teams), false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn team(name: &str, average_wn8: f64) -> TeamSummary {
+        TeamSummary {
+            name: name.to_string(),
+            average_wn8,
+            median_wn8: average_wn8,
+            spent: 0,
+            balance: 0,
+            tags: Vec::new(),
+            player_count: 1,
+        }
+    }
+
+    #[test]
+    fn median_of_even_and_odd() {
+        assert_eq!(median(&[]), 0.0);
+        assert_eq!(median(&[3000, 1000, 2000]), 2000.0);
+        assert_eq!(median(&[4000, 1000, 2000, 3000]), 2500.0);
+        assert_eq!(average(&[1000, 2000]), 1500.0);
+    }
+
+    #[test]
+    fn equal_teams_score_100() {
+        let teams = vec![team("a", 2000.0), team("b", 2000.0)];
+        assert_eq!(balance_score(&teams), 100.0);
+        assert!(outliers(&teams).is_empty());
+    }
+
+    #[test]
+    fn flags_outlier_team() {
+        let teams = vec![team("a", 2000.0), team("b", 2000.0), team("c", 2000.0), team("d", 3000.0)];
+        let flagged: Vec<&str> = outliers(&teams).iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(flagged, vec!["d"]);
+        assert!(balance_score(&teams) < 100.0);
+    }
+}
@@ -0,0 +1,731 @@
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time;
+use tracing::{info, info_span, instrument, warn, Instrument};
+
+use crate::auction::{self, Sale, Session};
+use crate::{bots, draft_board, final_draft_board, Board, Captain, Config, DraftState, Error};
+
+/// Where the auction loop reports to, Discord messages for the bot and
+/// stdout for the terminal draft
+pub trait DraftOutput {
+    /// Shows the draft board, replacing the previous one
+    fn board(&mut self, board: Board) -> impl Future<Output = Result<(), Error>> + Send;
+    /// Replaces the line under the board
+    fn status(&mut self, text: String) -> impl Future<Output = Result<(), Error>> + Send;
+    /// Announces a finished lot
+    fn sold(&mut self, sale: &Sale) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// Where a command's answer goes, a reply to the interaction for the bot
+pub trait CommandOutput {
+    /// Answers whoever ran the command, only them when `private`
+    fn reply(&mut self, text: String, private: bool) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+/// What the auction loop waits on between updates. Every tick counts as two
+/// seconds off the clock
+pub trait Clock {
+    fn tick(&mut self) -> impl Future<Output = ()> + Send;
+}
+
+/// Ticks every two seconds of real time
+pub struct IntervalClock(time::Interval);
+
+impl Default for IntervalClock {
+    fn default() -> Self {
+        Self(time::interval(Duration::from_secs(2)))
+    }
+}
+
+impl Clock for IntervalClock {
+    async fn tick(&mut self) {
+        self.0.tick().await;
+    }
+}
+
+/// Clock that only ticks when its `ClockHandle` says so
+pub struct ManualClock {
+    waiting: mpsc::Sender<()>,
+    ticks: mpsc::Receiver<()>,
+}
+
+pub struct ClockHandle {
+    waiting: mpsc::Receiver<()>,
+    ticks: mpsc::Sender<()>,
+    idle: bool,
+}
+
+pub fn manual_clock() -> (ManualClock, ClockHandle) {
+    let (waiting_tx, waiting_rx) = mpsc::channel(1);
+    let (ticks_tx, ticks_rx) = mpsc::channel(1);
+    let clock = ManualClock { waiting: waiting_tx, ticks: ticks_rx };
+    let handle = ClockHandle { waiting: waiting_rx, ticks: ticks_tx, idle: false };
+    (clock, handle)
+}
+
+impl Clock for ManualClock {
+    async fn tick(&mut self) {
+        if self.waiting.send(()).await.is_err() || self.ticks.recv().await.is_none() {
+            // the handle is gone, nothing will tick again
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl ClockHandle {
+    /// Waits until the loop is blocked on its next tick, false once it has finished
+    pub async fn idle(&mut self) -> bool {
+        if !self.idle {
+            self.idle = self.waiting.recv().await.is_some();
+        }
+        self.idle
+    }
+
+    /// Lets one tick through and waits for the loop to finish handling it
+    pub async fn tick(&mut self) -> bool {
+        if !self.idle().await || self.ticks.send(()).await.is_err() {
+            return false;
+        }
+        self.idle = false;
+        self.idle().await
+    }
+}
+
+/// One thing a `Recorder` was asked to show
+#[derive(Clone, Debug, PartialEq)]
+pub enum Sent {
+    Board(Board),
+    Status(String),
+    Sold { captain: String, player: String, price: u32, bids: u32 },
+    Reply { text: String, private: bool },
+}
+
+/// Keeps everything the auction loop and commands send, for tests and replays
+#[derive(Default)]
+pub struct Recorder {
+    pub sent: Vec<Sent>,
+}
+
+impl Recorder {
+    pub fn statuses(&self) -> Vec<&str> {
+        self.sent.iter().filter_map(|s| match s {
+            Sent::Status(text) => Some(text.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn sales(&self) -> Vec<(&str, &str, u32)> {
+        self.sent.iter().filter_map(|s| match s {
+            Sent::Sold { captain, player, price, .. } => Some((captain.as_str(), player.as_str(), *price)),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn replies(&self) -> Vec<&str> {
+        self.sent.iter().filter_map(|s| match s {
+            Sent::Reply { text, .. } => Some(text.as_str()),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn last_board(&self) -> Option<&Board> {
+        self.sent.iter().rev().find_map(|s| match s {
+            Sent::Board(board) => Some(board),
+            _ => None,
+        })
+    }
+}
+
+impl DraftOutput for Recorder {
+    async fn board(&mut self, board: Board) -> Result<(), Error> {
+        self.sent.push(Sent::Board(board));
+        Ok(())
+    }
+
+    async fn status(&mut self, text: String) -> Result<(), Error> {
+        self.sent.push(Sent::Status(text));
+        Ok(())
+    }
+
+    async fn sold(&mut self, sale: &Sale) -> Result<(), Error> {
+        self.sent.push(Sent::Sold {
+            captain: sale.captain.lock().await.name.clone(),
+            player: sale.player.lock().await.name.clone(),
+            price: sale.price,
+            bids: sale.bids,
+        });
+        Ok(())
+    }
+}
+
+impl CommandOutput for Recorder {
+    async fn reply(&mut self, text: String, private: bool) -> Result<(), Error> {
+        self.sent.push(Sent::Reply { text, private });
+        Ok(())
+    }
+}
+
+enum Queued {
+    Board(Board),
+    Status(String),
+    Sold(Sale),
+}
+
+/// Output for the auction loop that only queues, `deliver_queued` sends it
+/// on. The lot clock never waits on a slow or failing output
+pub struct OutputQueue(mpsc::UnboundedSender<Queued>);
+
+pub struct QueuedUpdates(mpsc::UnboundedReceiver<Queued>);
+
+pub fn output_queue() -> (OutputQueue, QueuedUpdates) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (OutputQueue(tx), QueuedUpdates(rx))
+}
+
+impl OutputQueue {
+    fn push(&self, update: Queued) -> Result<(), Error> {
+        self.0.send(update).map_err(|_| "draft output is gone".into())
+    }
+}
+
+impl DraftOutput for OutputQueue {
+    async fn board(&mut self, board: Board) -> Result<(), Error> {
+        self.push(Queued::Board(board))
+    }
+
+    async fn status(&mut self, text: String) -> Result<(), Error> {
+        self.push(Queued::Status(text))
+    }
+
+    async fn sold(&mut self, sale: &Sale) -> Result<(), Error> {
+        self.push(Queued::Sold(sale.clone()))
+    }
+}
+
+/// Sends queued updates to `output` until the `OutputQueue` is dropped.
+/// Statuses and sales go out in order, boards that piled up behind a slow
+/// delivery are skipped for the newest one
+pub async fn deliver_queued<O: DraftOutput + Send>(mut updates: QueuedUpdates, output: &mut O) {
+    while let Some(first) = updates.0.recv().await {
+        let mut batch = vec![first];
+        while let Ok(next) = updates.0.try_recv() {
+            batch.push(next);
+        }
+        let newest_board = batch.iter().rposition(|u| matches!(u, Queued::Board(_)));
+        for (i, update) in batch.into_iter().enumerate() {
+            match update {
+                Queued::Board(board) if Some(i) == newest_board => delivered(output.board(board).await),
+                Queued::Board(_) => {}
+                Queued::Status(text) => delivered(output.status(text).await),
+                Queued::Sold(sale) => delivered(output.sold(&sale).await),
+            }
+        }
+    }
+}
+
+/// "<winner> bid <amount> for <player>" for the lot being auctioned
+pub async fn bid_status(draft_state: &DraftState) -> Option<String> {
+    let winner = draft_state.current_winner.as_ref()?;
+    let player = draft_state.nominated_player.as_ref()?;
+    Some(format!("{} bid {} for {}",
+        winner.lock().await.name,
+        draft_state.current_bid,
+        player.lock().await.name,
+    ))
+}
+
+/// Output failures never stop the draft. Everything lives in the session, so
+/// the next update shows it all again
+fn delivered(result: Result<(), Error>) {
+    if let Err(e) = result {
+        warn!(error = %e, "draft update not delivered, carrying on");
+    }
+}
+
+/// Runs every round of `session`, letting bots act on every tick. `config`
+/// is read each tick so timings can change mid draft
+#[instrument(name = "draft", skip_all, fields(mock = session.mock))]
+pub async fn run_auction<O: DraftOutput + Send, C: Clock + Send>(
+    session: &Session,
+    config: &Mutex<Config>,
+    output: &mut O,
+    clock: &mut C,
+) {
+    let mut captains_main = session.captains.lock().await;
+    captains_main.shuffle(&mut thread_rng());
+    let captains = captains_main.clone();
+    drop(captains_main);
+    let team_size = config.lock().await.team_size;
+    let mut draft_state = session.draft_state.lock().await;
+    draft_state.draft_started = true;
+    let board = draft_board(&draft_state, &captains, &*config.lock().await).await;
+    drop(draft_state);
+    delivered(output.board(board).await);
+    let started = if session.mock { "Mock Draft Started" } else { "Draft Started" };
+    delivered(output.status(String::from(started)).await);
+    for i in 1..team_size+1 {
+        for captain in captains.iter() {
+            // keepers can fill a roster before the auction does
+            if captain.lock().await.players.len() as u32 >= team_size {
+                continue;
+            }
+            let config_now = config.lock().await.clone();
+            let players = session.players.lock().await.clone();
+            // nobody left they could nominate, the round would never start
+            if auction::eligible_players(&players, &config_now, captain).await.is_empty() {
+                let name = captain.lock().await.name.clone();
+                info!(round = i, captain = %name, "turn skipped, nobody eligible");
+                delivered(output.status(format!("{} can't take or afford anyone left, skipping their turn", name)).await);
+                continue;
+            }
+            let name = captain.lock().await.name.clone();
+            run_lot(session, config, output, clock, &captains, i, captain)
+                .instrument(info_span!("lot", round = i, captain = %name))
+                .await;
+        }
+    }
+    let captains = session.captains.lock().await.clone();
+    delivered(output.board(final_draft_board(captains).await).await);
+}
+
+/// One nomination and its countdown, ends when the lot is sold
+async fn run_lot<O: DraftOutput + Send, C: Clock + Send>(
+    session: &Session,
+    config: &Mutex<Config>,
+    output: &mut O,
+    clock: &mut C,
+    captains: &[Arc<Mutex<Captain>>],
+    round: u32,
+    captain: &Arc<Mutex<Captain>>,
+) {
+    let config_now = config.lock().await.clone();
+    let mut draft_state = session.draft_state.lock().await;
+    draft_state.start_round(round, captain.clone(), &config_now);
+    let board = draft_board(&draft_state, captains, &config_now).await;
+    drop(draft_state);
+    delivered(output.board(board).await);
+    loop {
+        clock.tick().await;
+        let config_now = config.lock().await.clone();
+        bots::run_bots(session, &config_now).await;
+        let mut draft_state = session.draft_state.lock().await;
+        if draft_state.nominated_player.is_some() {
+            break;
+        }
+        if config_now.round_time != draft_state.time {
+            draft_state.time = config_now.round_time;
+            let board = draft_board(&draft_state, captains, &config_now).await;
+            drop(draft_state);
+            delivered(output.board(board).await);
+        }
+    }
+    let config_now = config.lock().await.clone();
+    let draft_state = session.draft_state.lock().await;
+    let board = draft_board(&draft_state, captains, &config_now).await;
+    let status = bid_status(&draft_state).await;
+    drop(draft_state);
+    if let Some(status) = status {
+        delivered(output.status(status).await);
+    }
+    delivered(output.board(board).await);
+    let mut time_left: u32 = config_now.round_time;
+    while time_left > 0 {
+        clock.tick().await;
+        let config_now = config.lock().await.clone();
+        bots::run_bots(session, &config_now).await;
+        let mut draft_state = session.draft_state.lock().await;
+        if !draft_state.paused {
+            if !time_left.is_multiple_of(2) {
+                time_left +=1;
+            }
+            time_left -= 2;
+        }
+        let mut status = None;
+        if draft_state.bid_placed {
+            time_left += config_now.bid_add_time;
+            draft_state.bid_placed = false;
+            status = bid_status(&draft_state).await;
+        }
+        draft_state.time = time_left;
+        let board = draft_board(&draft_state, captains, &config_now).await;
+        drop(draft_state);
+        delivered(output.board(board).await);
+        if let Some(status) = status {
+            delivered(output.status(status).await);
+        }
+    }
+    let mut draft_state = session.draft_state.lock().await;
+    let lot = draft_state.nominated_player.clone().zip(draft_state.current_winner.clone());
+    let sale = auction::sell(&mut draft_state).await;
+    drop(draft_state);
+    match (sale, lot) {
+        (Some(sale), _) => delivered(output.sold(&sale).await),
+        (None, Some((player, winner))) => {
+            let player = player.lock().await.name.clone();
+            let winner = winner.lock().await.name.clone();
+            delivered(output.status(format!("{} could not pay for {}, they go back to the pool", winner, player)).await);
+        }
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use crate::{commands, Player};
+
+    /// Plays one human captain through the commands: nominates the best
+    /// player on its turn and otherwise raises by 5 until `limit`. Returns
+    /// the replies it got once the draft is over
+    async fn captain_task(session: Arc<Session>, config: Config, name: &'static str, limit: u32) -> Recorder {
+        let captain = session.find_captain_by_name(name).await.unwrap();
+        let author = captain.lock().await.discord_id;
+        let mut replies = Recorder::default();
+        let mut interval = time::interval(Duration::from_millis(700));
+        loop {
+            interval.tick().await;
+            let draft_state = session.draft_state.lock().await;
+            if draft_state.draft_finished {
+                return replies;
+            }
+            let my_turn = draft_state.round_captain.as_ref().is_some_and(|c| Arc::ptr_eq(c, &captain));
+            let nominated = draft_state.nominated_player.is_some();
+            let winning = draft_state.current_winner.as_ref().is_some_and(|c| Arc::ptr_eq(c, &captain));
+            let next = draft_state.current_bid + 5;
+            drop(draft_state);
+            if my_turn && !nominated {
+                let players = session.players.lock().await.clone();
+                let mut best = None;
+                for player in players {
+                    let p1 = player.lock().await;
+                    if !p1.picked && best.as_ref().is_none_or(|(wn8, _)| p1.recent_wn8 > *wn8) {
+                        best = Some((p1.recent_wn8, player.clone()));
+                    }
+                }
+                if let Some((_, player)) = best {
+                    let player = player.lock().await.name.clone();
+                    let _ = commands::pick(&session, &config, author, &player, None, &mut replies).await;
+                }
+            } else if nominated && !winning && next <= limit {
+                let _ = commands::bid(&session, &config, author, next, &mut replies).await;
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scripted_draft_with_concurrent_captains() {
+        let config = Config { team_size: 2, starting_balance: 200, round_time: 10, bid_add_time: 4, ..Config::default() };
+        let players = (0..6).map(|i| {
+            let mut p = Player::new(format!("p{}", i), Vec::new());
+            p.recent_wn8 = 1000 + 200 * i;
+            Arc::new(Mutex::new(p))
+        }).collect();
+        let session = Arc::new(Session::new(players));
+        let limits = [("low", 30), ("mid", 50), ("high", 70)];
+        for (id, (name, _)) in limits.iter().enumerate() {
+            auction::add_captain(&session, &config, id as u64 + 1, name, None, None, None).await.unwrap();
+        }
+        let tasks: Vec<_> = limits.iter()
+            .map(|(name, limit)| tokio::spawn(captain_task(session.clone(), config.clone(), name, *limit)))
+            .collect();
+        let mut recorder = Recorder::default();
+        let mut clock = IntervalClock::default();
+        run_auction(&session, &Mutex::new(config.clone()), &mut recorder, &mut clock).await;
+        session.draft_state.lock().await.draft_finished = true;
+        let mut replies = Vec::new();
+        for task in tasks {
+            replies.push(task.await.unwrap());
+        }
+
+        let statuses = recorder.statuses();
+        assert_eq!(statuses[0], "Draft Started");
+        assert!(statuses.iter().any(|s| s.starts_with("high bid")), "{:?}", statuses);
+        // everyone bids on the first lot, high takes it once mid cannot raise past 50
+        let sales = recorder.sales();
+        let (winner, player, price) = sales[0];
+        assert_eq!((winner, player), ("high", "p5"));
+        assert!((46..=55).contains(&price), "{}", price);
+        let mut sold = 0;
+        for captain in session.captains.lock().await.iter() {
+            let c1 = captain.lock().await;
+            let spent: u32 = sales.iter().filter(|s| s.0 == c1.name).map(|s| s.2).sum();
+            assert_eq!(c1.balance + spent, config.starting_balance, "{}", c1.name);
+            assert!(c1.players.len() as u32 <= config.team_size);
+            for player in c1.players.iter() {
+                let name = player.lock().await.name.clone();
+                assert!(sales.iter().any(|s| s.0 == c1.name && s.1 == name), "{} {}", c1.name, name);
+            }
+            sold += c1.players.len();
+        }
+        assert_eq!(sold, sales.len());
+        let last = recorder.last_board().unwrap();
+        assert!(last.fields.iter().any(|(name, value, _)| name == "high" && value.contains("p5")), "{:?}", last);
+        // every lot came from a /pick, and replies only went to the captain who asked
+        let unpaid = statuses.iter().filter(|s| s.contains("could not pay")).count();
+        let mut nominated = 0;
+        for replies in replies.iter() {
+            nominated += replies.replies().iter().filter(|r| r.starts_with("You nominated")).count();
+            assert!(replies.sent.iter().all(|s| matches!(s, Sent::Reply { private: true, .. })));
+        }
+        assert_eq!(nominated, sales.len() + unpaid);
+        assert!(replies[2].replies().iter().any(|r| r.starts_with("You Bid") && r.ends_with("p5")));
+    }
+
+    /// Draft between captains a and b with one lot each, driven by a manual clock.
+    /// Returns the round captain once the first lot is waiting for a nomination
+    async fn timed_draft(config: Config) -> (Arc<Session>, ClockHandle, Arc<Mutex<crate::Captain>>, Arc<Mutex<crate::Captain>>) {
+        let players = ["x", "y"].iter()
+            .map(|n| Arc::new(Mutex::new(Player::new(n.to_string(), Vec::new()))))
+            .collect();
+        let session = Arc::new(Session::new(players));
+        for (id, name) in ["a", "b"].iter().enumerate() {
+            auction::add_captain(&session, &config, id as u64 + 1, name, None, None, None).await.unwrap();
+        }
+        let (mut clock, mut handle) = manual_clock();
+        let runner = session.clone();
+        tokio::spawn(async move {
+            let config = Mutex::new(config);
+            run_auction(&runner, &config, &mut Recorder::default(), &mut clock).await
+        });
+        assert!(handle.idle().await);
+        let nominator = session.draft_state.lock().await.round_captain.clone().unwrap();
+        let a = session.find_captain(1).await.unwrap();
+        let b = session.find_captain(2).await.unwrap();
+        let other = if Arc::ptr_eq(&nominator, &a) { b } else { a };
+        (session, handle, nominator, other)
+    }
+
+    async fn time_left(session: &Session) -> u32 {
+        session.draft_state.lock().await.time
+    }
+
+    async fn roster(captain: &Arc<Mutex<crate::Captain>>) -> usize {
+        captain.lock().await.players.len()
+    }
+
+    fn timed_config() -> Config {
+        Config { team_size: 1, round_time: 10, bid_add_time: 4, ..Config::default() }
+    }
+
+    #[tokio::test]
+    async fn lot_expires_after_round_time() {
+        let config = timed_config();
+        let (session, mut clock, nominator, _) = timed_draft(config.clone()).await;
+        // nobody nominates, the clock waits
+        for _ in 0..3 {
+            assert!(clock.tick().await);
+            assert_eq!(time_left(&session).await, 10);
+        }
+        let x = session.find_available("x").await.unwrap();
+        auction::nominate(&session, &config, &nominator, &x, None).await.unwrap();
+        assert!(clock.tick().await);
+        for left in [8, 6, 4, 2] {
+            assert!(clock.tick().await);
+            assert_eq!(time_left(&session).await, left);
+            assert_eq!(roster(&nominator).await, 0);
+        }
+        assert!(clock.tick().await);
+        assert_eq!(roster(&nominator).await, 1);
+        assert_eq!(nominator.lock().await.balance, config.starting_balance - config.min_bid);
+    }
+
+    #[tokio::test]
+    async fn last_second_bid_extends_the_lot() {
+        let config = timed_config();
+        let (session, mut clock, nominator, other) = timed_draft(config.clone()).await;
+        let x = session.find_available("x").await.unwrap();
+        auction::nominate(&session, &config, &nominator, &x, None).await.unwrap();
+        for _ in 0..5 {
+            assert!(clock.tick().await);
+        }
+        assert_eq!(time_left(&session).await, 2);
+        auction::place_bid(&session, &config, &other, 15).await.unwrap();
+        // the clock runs out on this tick, the bid puts time back on
+        assert!(clock.tick().await);
+        assert_eq!(time_left(&session).await, 4);
+        assert_eq!(roster(&other).await, 0);
+        assert!(clock.tick().await);
+        assert_eq!(roster(&other).await, 0);
+        // the nominator went first, so with `other` full the draft is over
+        assert!(!clock.tick().await);
+        assert_eq!(roster(&other).await, 1);
+        assert_eq!(roster(&nominator).await, 0);
+        assert_eq!(other.lock().await.balance, config.starting_balance - 15);
+    }
+
+    #[tokio::test]
+    async fn every_bid_extends_again() {
+        let config = timed_config();
+        let (session, mut clock, nominator, other) = timed_draft(config.clone()).await;
+        let x = session.find_available("x").await.unwrap();
+        auction::nominate(&session, &config, &nominator, &x, None).await.unwrap();
+        assert!(clock.tick().await);
+        let bids = [(&other, 15, 12), (&nominator, 20, 14), (&other, 25, 16)];
+        for (captain, amount, left) in bids {
+            auction::place_bid(&session, &config, captain, amount).await.unwrap();
+            assert!(clock.tick().await);
+            assert_eq!(time_left(&session).await, left);
+        }
+        for _ in 0..7 {
+            assert!(clock.tick().await);
+        }
+        assert_eq!(time_left(&session).await, 2);
+        assert_eq!(roster(&other).await, 0);
+        assert!(!clock.tick().await);
+        assert_eq!(roster(&other).await, 1);
+        assert_eq!(other.lock().await.balance, config.starting_balance - 25);
+    }
+
+    #[tokio::test]
+    async fn broke_captains_are_skipped_and_lone_nominations_sell() {
+        let tier = crate::Tier { name: String::from("a"), min_price: 30, min_count: 0, max_count: 2 };
+        let config = Config { team_size: 2, tiers: vec![tier], ..timed_config() };
+        let players = ["x", "y", "z"].iter().map(|n| {
+            let mut p = Player::new(n.to_string(), Vec::new());
+            p.tier = Some(String::from("a"));
+            Arc::new(Mutex::new(p))
+        }).collect();
+        let session = Arc::new(Session::new(players));
+        auction::add_captain(&session, &config, 1, "a", None, None, None).await.unwrap();
+        // b plays for 180 and has $20 left, below every player's minimum
+        auction::add_captain(&session, &config, 2, "b", None, None, Some(180)).await.unwrap();
+        let a = session.find_captain(1).await.unwrap();
+        let (mut clock, mut handle) = manual_clock();
+        let runner = session.clone();
+        let draft_config = config.clone();
+        let task = tokio::spawn(async move {
+            let mut recorder = Recorder::default();
+            run_auction(&runner, &Mutex::new(draft_config), &mut recorder, &mut clock).await;
+            recorder
+        });
+        while handle.tick().await {
+            let draft_state = session.draft_state.lock().await;
+            let a_to_nominate = draft_state.nominated_player.is_none()
+                && draft_state.round_captain.as_ref().is_some_and(|c| Arc::ptr_eq(c, &a));
+            drop(draft_state);
+            if a_to_nominate {
+                let player = session.nominee("x").await.or(session.nominee("y").await).unwrap();
+                auction::nominate(&session, &config, &a, &player, None).await.unwrap();
+            }
+        }
+        let recorder = task.await.unwrap();
+        let skips = recorder.statuses().iter().filter(|s| s.starts_with("b can't take")).count();
+        assert_eq!(skips, 2, "{:?}", recorder.statuses());
+        assert_eq!(recorder.sales(), vec![("a", "x", 30), ("a", "y", 30)]);
+        assert!(recorder.sent.iter().all(|s| !matches!(s, Sent::Sold { bids, .. } if *bids > 0)));
+        assert_eq!(a.lock().await.balance, config.starting_balance - 60);
+    }
+
+    /// Refuses every update, like Discord during an outage
+    #[derive(Default)]
+    struct Down {
+        attempts: u32,
+        sales: usize,
+    }
+
+    impl DraftOutput for Down {
+        async fn board(&mut self, _board: Board) -> Result<(), Error> {
+            self.attempts += 1;
+            Err("board not delivered".into())
+        }
+
+        async fn status(&mut self, _text: String) -> Result<(), Error> {
+            self.attempts += 1;
+            Err("status not delivered".into())
+        }
+
+        async fn sold(&mut self, _sale: &Sale) -> Result<(), Error> {
+            self.sales += 1;
+            Err("sale not delivered".into())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn draft_finishes_when_nothing_is_delivered() {
+        let config = Config { team_size: 2, starting_balance: 100, round_time: 6, ..Config::default() };
+        let players = (0..4).map(|i| Arc::new(Mutex::new(Player::new(format!("p{}", i), Vec::new())))).collect();
+        let session = Session::new(players);
+        for (id, name) in ["a", "b"].iter().enumerate() {
+            auction::add_captain(&session, &config, id as u64 + 1, name, None, None, None).await.unwrap();
+            let captain = session.find_captain(id as u64 + 1).await.unwrap();
+            session.bots.lock().await.push(bots::Bot::new(captain, bots::Strategy::Value, id as u64));
+        }
+        let mut output = Down::default();
+        run_auction(&session, &Mutex::new(config.clone()), &mut output, &mut IntervalClock::default()).await;
+        assert!(output.attempts > 0);
+        // the failed updates did not stop any lot
+        assert!(output.sales >= 3, "{}", output.sales);
+        let mut rostered = 0;
+        for captain in session.captains.lock().await.iter() {
+            let c1 = captain.lock().await;
+            let mut spent = 0;
+            for player in c1.players.iter() {
+                spent += player.lock().await.purchase.as_ref().unwrap().price;
+            }
+            assert_eq!(c1.balance + spent, config.starting_balance, "{}", c1.name);
+            rostered += c1.players.len();
+        }
+        assert_eq!(rostered, output.sales);
+    }
+
+    /// Takes `delay` for every board, like Discord retrying an edit
+    struct Slow {
+        recorder: Recorder,
+        delay: Duration,
+    }
+
+    impl DraftOutput for Slow {
+        async fn board(&mut self, board: Board) -> Result<(), Error> {
+            time::sleep(self.delay).await;
+            self.recorder.board(board).await
+        }
+
+        async fn status(&mut self, text: String) -> Result<(), Error> {
+            self.recorder.status(text).await
+        }
+
+        async fn sold(&mut self, sale: &Sale) -> Result<(), Error> {
+            self.recorder.sold(sale).await
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_output_does_not_hold_up_the_clock() {
+        // nobody can outbid a nomination, so every lot takes the same few ticks
+        let config = Config { team_size: 2, starting_balance: 2 * Config::default().min_bid, round_time: 6, ..Config::default() };
+        let players = (0..4).map(|i| Arc::new(Mutex::new(Player::new(format!("p{}", i), Vec::new())))).collect();
+        let session = Arc::new(Session::new(players));
+        for (id, name) in ["a", "b"].iter().enumerate() {
+            auction::add_captain(&session, &config, id as u64 + 1, name, None, None, None).await.unwrap();
+            let captain = session.find_captain(id as u64 + 1).await.unwrap();
+            session.bots.lock().await.push(bots::Bot::new(captain, bots::Strategy::Value, id as u64));
+        }
+        let (mut queue, updates) = output_queue();
+        let mut output = Slow { recorder: Recorder::default(), delay: Duration::from_secs(30) };
+        let started = time::Instant::now();
+        let runner = session.clone();
+        let draft_config = config.clone();
+        let auction = async move {
+            run_auction(&runner, &Mutex::new(draft_config), &mut queue, &mut IntervalClock::default()).await;
+            started.elapsed()
+        };
+        let (auction_time, ()) = tokio::join!(auction, deliver_queued(updates, &mut output));
+        // four lots of a few ticks each, a 30s board would have stretched every one
+        assert!(auction_time < Duration::from_secs(60), "{:?}", auction_time);
+        let recorder = output.recorder;
+        assert_eq!(recorder.sales().len(), 4);
+        let boards = recorder.sent.iter().filter(|s| matches!(s, Sent::Board(_))).count();
+        assert!(boards < 10, "{}", boards);
+        // the board everyone sees last is the final one
+        let last = recorder.last_board().unwrap();
+        for (captain, player, _) in recorder.sales() {
+            assert!(last.fields.iter().any(|(name, value, _)| name == captain && value.contains(player)), "{:?}", last);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::auction::{self, Session};
+use crate::bots::{self, Strategy};
+use crate::{report, Captain, Config, Player};
+
+/// What happened in one simulated draft
+pub struct DraftRun {
+    pub prices: Vec<u32>,
+    /// Sale prices by round, index 0 is round 1
+    pub round_prices: Vec<Vec<u32>>,
+    /// Estimated length, every lot runs `round_time` plus `bid_add_time` per bid
+    pub seconds: u32,
+    pub balance_score: f64,
+    /// Roster slots still open when the last round ends, left for free agency
+    pub empty_slots: u32,
+    pub leftover: Vec<u32>,
+}
+
+/// Pool and bot captains every run starts from
+pub async fn base_session(players: Vec<Arc<Mutex<Player>>>, captains: u32, config: &Config) -> Session {
+    let session = Session::new(players);
+    for id in 1..=captains {
+        let captain = Captain::new(id as u64, format!("Bot {}", id), config.starting_balance);
+        session.captains.lock().await.push(Arc::new(Mutex::new(captain)));
+    }
+    session
+}
+
+/// Runs a whole draft between bots on a copy of `base`, without timers
+pub async fn run_draft(base: &Session, config: &Config, strategy: Option<Strategy>, seed: u64) -> DraftRun {
+    let session = Session::mock_copy(base, &[], strategy, seed).await;
+    let mut captains = session.captains.lock().await.clone();
+    captains.shuffle(&mut StdRng::seed_from_u64(seed));
+    let mut run = DraftRun {
+        prices: Vec::new(),
+        round_prices: vec![Vec::new(); config.team_size as usize],
+        seconds: 0,
+        balance_score: 0.0,
+        empty_slots: 0,
+        leftover: Vec::new(),
+    };
+    for round in 1..=config.team_size {
+        for captain in captains.iter() {
+            if captain.lock().await.players.len() as u32 >= config.team_size {
+                continue;
+            }
+            session.draft_state.lock().await.start_round(round, captain.clone(), config);
+            if !bots::run_bots(&session, config).await {
+                continue;
+            }
+            let mut bids = 0;
+            while bots::run_bots(&session, config).await {
+                bids += 1;
+            }
+            run.seconds += config.round_time + bids * config.bid_add_time;
+            if let Some(sale) = auction::sell(&mut *session.draft_state.lock().await).await {
+                run.prices.push(sale.price);
+                run.round_prices[round as usize - 1].push(sale.price);
+            }
+        }
+    }
+    let teams = report::summarize(&captains).await;
+    run.balance_score = report::balance_score(&teams);
+    run.leftover = teams.iter().map(|t| t.balance).collect();
+    run.empty_slots = teams.iter().map(|t| config.team_size.saturating_sub(t.player_count as u32)).sum();
+    run
+}
+
+/// Nearest rank percentile of sorted values, `p` from 0 to 100
+pub fn percentile(sorted: &[u32], p: f64) -> u32 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Price, timing and balance statistics over many runs
+pub fn summary(runs: &[DraftRun], config: &Config) -> String {
+    let mut prices: Vec<u32> = runs.iter().flat_map(|r| r.prices.iter().copied()).collect();
+    prices.sort_unstable();
+    let mut text = format!("{} runs, team size {}, starting balance ${}, min bid ${}\n",
+        runs.len(),
+        config.team_size,
+        config.starting_balance,
+        config.min_bid,
+    );
+    text += &format!("\nPrices: avg ${:.1}, min ${}, p10 ${}, p25 ${}, median ${}, p75 ${}, p90 ${}, max ${}\n",
+        report::average(&prices),
+        percentile(&prices, 0.0),
+        percentile(&prices, 10.0),
+        percentile(&prices, 25.0),
+        percentile(&prices, 50.0),
+        percentile(&prices, 75.0),
+        percentile(&prices, 90.0),
+        percentile(&prices, 100.0),
+    );
+    for round in 0..config.team_size as usize {
+        let round_prices: Vec<u32> = runs.iter()
+            .flat_map(|r| r.round_prices.get(round).into_iter().flatten().copied())
+            .collect();
+        text += &format!("  Round {}: avg ${:.1}\n", round + 1, report::average(&round_prices));
+    }
+    let mut minutes: Vec<u32> = runs.iter().map(|r| r.seconds / 60).collect();
+    minutes.sort_unstable();
+    text += &format!("\nLength: avg {:.0} min, min {} min, max {} min\n",
+        report::average(&minutes),
+        percentile(&minutes, 0.0),
+        percentile(&minutes, 100.0),
+    );
+    let mut scores: Vec<u32> = runs.iter().map(|r| r.balance_score.round() as u32).collect();
+    scores.sort_unstable();
+    text += &format!("Balance Score: avg {:.1}, p10 {}, worst {}\n",
+        report::average(&scores),
+        percentile(&scores, 10.0),
+        percentile(&scores, 0.0),
+    );
+    let leftover: Vec<u32> = runs.iter().flat_map(|r| r.leftover.iter().copied()).collect();
+    text += &format!("Unspent per team: avg ${:.1}\n", report::average(&leftover));
+    let empty: u32 = runs.iter().map(|r| r.empty_slots).sum();
+    if empty > 0 {
+        text += &format!("Open slots after the last round: avg {:.2} per draft\n", empty as f64 / runs.len() as f64);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(size: u32) -> Vec<Arc<Mutex<Player>>> {
+        (0..size).map(|i| {
+            let mut p = Player::new(format!("p{}", i), Vec::new());
+            p.recent_wn8 = 1000 + 150 * i;
+            Arc::new(Mutex::new(p))
+        }).collect()
+    }
+
+    #[test]
+    fn percentiles() {
+        let values = [10, 20, 30, 40, 50];
+        assert_eq!(percentile(&values, 0.0), 10);
+        assert_eq!(percentile(&values, 50.0), 30);
+        assert_eq!(percentile(&values, 90.0), 50);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    #[tokio::test]
+    async fn bots_fill_every_roster() {
+        let config = Config { team_size: 4, starting_balance: 100, ..Config::default() };
+        let base = base_session(pool(14), 3, &config).await;
+        let mut runs = Vec::new();
+        for seed in 0..5 {
+            runs.push(run_draft(&base, &config, None, seed).await);
+        }
+        for run in runs.iter() {
+            assert_eq!(run.prices.len() as u32 + run.empty_slots, 12);
+            assert!(run.prices.iter().all(|p| *p >= config.min_bid));
+            assert!(run.seconds >= 12 * config.round_time);
+        }
+        // the base pool is never drafted
+        assert!(base.find_available("p0").await.is_some());
+        let text = summary(&runs, &config);
+        assert!(text.contains("5 runs"), "{}", text);
+        assert!(text.contains("Round 4"), "{}", text);
+    }
+
+    #[tokio::test]
+    async fn small_pool_leaves_slots_empty() {
+        let config = Config { team_size: 4, ..Config::default() };
+        let base = base_session(pool(6), 2, &config).await;
+        let run = run_draft(&base, &config, Some(Strategy::Pacing), 1).await;
+        assert_eq!(run.prices.len(), 6);
+        assert_eq!(run.empty_slots, 2);
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::constraints;
+use crate::error::DraftError;
+use crate::{Captain, Config, Player};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TradeStatus {
+    Proposed,
+    /// Accepted by both captains, waiting on an admin
+    AwaitingApproval,
+}
+
+/// `from` sends `give` and `give_money` to `to` in exchange for `receive` and
+/// `receive_money`
+pub struct Trade {
+    pub id: u32,
+    pub from: Arc<Mutex<Captain>>,
+    pub to: Arc<Mutex<Captain>>,
+    pub give: Vec<Arc<Mutex<Player>>>,
+    pub receive: Vec<Arc<Mutex<Player>>>,
+    pub give_money: u32,
+    pub receive_money: u32,
+    pub status: TradeStatus,
+}
+
+async fn names(players: &[Arc<Mutex<Player>>]) -> Vec<String> {
+    let mut names = Vec::new();
+    for player in players {
+        names.push(player.lock().await.name.clone());
+    }
+    names
+}
+
+fn side(names: Vec<String>, money: u32) -> String {
+    let mut parts = names;
+    if money > 0 {
+        parts.push(format!("${}", money));
+    }
+    if parts.is_empty() {
+        return String::from("nothing");
+    }
+    parts.join(", ")
+}
+
+impl Trade {
+    pub async fn describe(&self) -> String {
+        format!("Trade #{}: {} gives {} to {} for {}",
+            self.id,
+            self.from.lock().await.name,
+            side(names(&self.give).await, self.give_money),
+            self.to.lock().await.name,
+            side(names(&self.receive).await, self.receive_money),
+        )
+    }
+
+    pub fn involves(&self, captain: &Arc<Mutex<Captain>>) -> bool {
+        Arc::ptr_eq(&self.from, captain) || Arc::ptr_eq(&self.to, captain)
+    }
+
+    /// Checks both rosters would still be legal after the trade, including
+    /// tier maximums and the configured roster constraints. `wn8_floor` is the weakest free
+    /// agent left, for rosters with open slots under a WN8 cap
+    pub async fn validate(&self, config: &Config, wn8_floor: u32) -> Result<(), String> {
+        if Arc::ptr_eq(&self.from, &self.to) {
+            return Err(String::from("Cannot trade with yourself"));
+        }
+        if self.give.is_empty() && self.receive.is_empty() && self.give_money == 0 && self.receive_money == 0 {
+            return Err(String::from("Trade is empty"));
+        }
+        let listed: Vec<_> = self.give.iter().chain(self.receive.iter()).collect();
+        for (i, player) in listed.iter().enumerate() {
+            if listed[..i].iter().any(|p| Arc::ptr_eq(p, player)) {
+                return Err(format!("{} is listed twice", player.lock().await.name));
+            }
+        }
+        for (captain, out, incoming, out_money) in [
+            (&self.from, &self.give, &self.receive, self.give_money),
+            (&self.to, &self.receive, &self.give, self.receive_money),
+        ] {
+            let c1 = captain.lock().await;
+            for player in out {
+                if !c1.players.iter().any(|p| Arc::ptr_eq(p, player)) {
+                    let name = player.lock().await.name.clone();
+                    return Err(format!("{} is not on {}", name, c1.name));
+                }
+            }
+            let roster = c1.players.len().checked_sub(out.len())
+                .ok_or_else(|| format!("{} doesn't have that many players", c1.name))?
+                + incoming.len();
+            let Some(open_slots) = config.team_size.checked_sub(roster as u32) else {
+                return Err(format!("{} would have {} players, team size is {}", c1.name, roster, config.team_size));
+            };
+            let mut counts = c1.counts.clone();
+            for player in out {
+                counts.remove(&*player.lock().await);
+            }
+            for player in incoming {
+                counts.add(&*player.lock().await);
+            }
+            for tier in config.tiers.iter() {
+                if counts.tier(&tier.name) > tier.max_count {
+                    return Err(format!("{} would have {} tier {} players, the most is {}",
+                        c1.name, counts.tier(&tier.name), tier.name, tier.max_count));
+                }
+            }
+            if let Err(e) = constraints::check_all(&config.constraints, &counts, open_slots, wn8_floor) {
+                return Err(format!("{}: {}", c1.name, e));
+            }
+            if c1.balance < out_money {
+                return Err(format!("{} only has ${}", c1.name, c1.balance));
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves players and money, call `validate` first. Like `validate` it
+    /// locks each captain before their players. Callers hold one lock across
+    /// both, so two trades never pass validation against the same rosters.
+    /// Money moves first, nothing changes when either side can't pay
+    pub async fn execute(&self) -> Result<(), DraftError> {
+        let mut from = self.from.lock().await;
+        let mut to = self.to.lock().await;
+        let from_balance = from.balance.checked_sub(self.give_money)
+            .and_then(|b| b.checked_add(self.receive_money))
+            .ok_or_else(|| DraftError::TradeUnaffordable(from.name.clone()))?;
+        let to_balance = to.balance.checked_sub(self.receive_money)
+            .and_then(|b| b.checked_add(self.give_money))
+            .ok_or_else(|| DraftError::TradeUnaffordable(to.name.clone()))?;
+        from.balance = from_balance;
+        to.balance = to_balance;
+        drop(to);
+        drop(from);
+        for (player, from, to) in self.give.iter().map(|p| (p, &self.from, &self.to))
+            .chain(self.receive.iter().map(|p| (p, &self.to, &self.from)))
+        {
+            let mut c_from = from.lock().await;
+            let mut p1 = player.lock().await;
+            c_from.players.retain(|p| !Arc::ptr_eq(p, player));
+            c_from.uncount_player(&p1);
+            p1.team = Some(to.clone());
+            drop(p1);
+            drop(c_from);
+            let mut c_to = to.lock().await;
+            let p1 = player.lock().await;
+            c_to.count_player(&p1);
+            drop(p1);
+            c_to.players.push(player.clone());
+        }
+        self.from.lock().await.recount().await;
+        self.to.lock().await.recount().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::add_player_to_captain;
+
+    async fn setup() -> (Arc<Mutex<Captain>>, Arc<Mutex<Captain>>, Vec<Arc<Mutex<Player>>>) {
+        let a = Arc::new(Mutex::new(Captain::new(1, String::from("a"), 50)));
+        let b = Arc::new(Mutex::new(Captain::new(2, String::from("b"), 50)));
+        let players: Vec<Arc<Mutex<Player>>> = ["x", "y", "z"].iter()
+            .map(|n| Arc::new(Mutex::new(Player::new(n.to_string(), if *n == "x" { vec![String::from("legio")] } else { Vec::new() }))))
+            .collect();
+        add_player_to_captain(a.clone(), players[0].clone(), 10, 1).await;
+        add_player_to_captain(b.clone(), players[1].clone(), 10, 1).await;
+        add_player_to_captain(b.clone(), players[2].clone(), 10, 2).await;
+        (a, b, players)
+    }
+
+    fn trade(a: &Arc<Mutex<Captain>>, b: &Arc<Mutex<Captain>>, give: Vec<Arc<Mutex<Player>>>, receive: Vec<Arc<Mutex<Player>>>, give_money: u32) -> Trade {
+        Trade {
+            id: 1,
+            from: a.clone(),
+            to: b.clone(),
+            give,
+            receive,
+            give_money,
+            receive_money: 0,
+            status: TradeStatus::Proposed,
+        }
+    }
+
+    #[tokio::test]
+    async fn swaps_players_and_money() {
+        let (a, b, players) = setup().await;
+        let t = trade(&a, &b, vec![players[0].clone()], vec![players[1].clone(), players[2].clone()], 5);
+        t.validate(&Config::default(), 0).await.unwrap();
+        t.execute().await.unwrap();
+        assert_eq!(a.lock().await.players.len(), 2);
+        assert_eq!(a.lock().await.balance, 45);
+        assert_eq!(a.lock().await.counts.tag("legio"), 0);
+        assert_eq!(b.lock().await.players.len(), 1);
+        assert_eq!(b.lock().await.balance, 55);
+        assert_eq!(b.lock().await.counts.tag("legio"), 1);
+        assert!(Arc::ptr_eq(players[0].lock().await.team.as_ref().unwrap(), &b));
+    }
+
+    #[tokio::test]
+    async fn rejects_illegal_trades() {
+        let (a, b, players) = setup().await;
+        let config = Config::default();
+        let not_owned = trade(&a, &b, vec![players[1].clone()], vec![], 0);
+        assert!(not_owned.validate(&config, 0).await.is_err());
+        let twice = trade(&a, &b, vec![players[0].clone(), players[0].clone()], vec![], 0);
+        assert_eq!(twice.validate(&config, 0).await.unwrap_err(), "x is listed twice");
+        let too_poor = trade(&a, &b, vec![], vec![players[1].clone()], 60);
+        assert!(too_poor.validate(&config, 0).await.is_err());
+        let small = Config { team_size: 1, ..Config::default() };
+        let too_big = trade(&a, &b, vec![], vec![players[1].clone()], 0);
+        assert!(too_big.validate(&small, 0).await.is_err());
+        let no_legio = Config {
+            constraints: vec!["max_tag:legio:0".parse().unwrap()],
+            ..Config::default()
+        };
+        let legio = trade(&b, &a, vec![], vec![players[0].clone()], 0);
+        let err = legio.validate(&no_legio, 0).await.unwrap_err();
+        assert!(err.contains("legio"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn rejects_trades_over_a_tier_maximum() {
+        let (a, b, players) = setup().await;
+        let config = Config {
+            tiers: vec![crate::Tier { name: String::from("s"), min_price: 10, min_count: 0, max_count: 1 }],
+            ..Config::default()
+        };
+        for player in players[..2].iter() {
+            player.lock().await.tier = Some(String::from("s"));
+        }
+        for captain in [&a, &b] {
+            captain.lock().await.recount().await;
+        }
+        let crowded = trade(&a, &b, vec![], vec![players[1].clone()], 0);
+        assert_eq!(crowded.validate(&config, 0).await.unwrap_err(), "a would have 2 tier s players, the most is 1");
+        let swap = trade(&a, &b, vec![players[0].clone()], vec![players[1].clone()], 0);
+        swap.validate(&config, 0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn execute_refuses_a_balance_that_would_go_negative() {
+        let (a, b, players) = setup().await;
+        let t = trade(&a, &b, vec![], vec![players[1].clone()], 30);
+        t.validate(&Config::default(), 0).await.unwrap();
+        // a spent money between validating and executing
+        a.lock().await.balance = 20;
+        assert_eq!(t.execute().await, Err(DraftError::TradeUnaffordable(String::from("a"))));
+        assert_eq!((a.lock().await.balance, b.lock().await.balance), (20, 50));
+        assert_eq!(b.lock().await.players.len(), 2);
+    }
+}
@@ -0,0 +1,423 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::wn8::{PlayerStats, TankStats};
+use crate::{unix_now, Error};
+
+/// The API accepts at most 100 ids or nicknames per request
+const BATCH_SIZE: usize = 100;
+/// Snapshots younger than this many seconds are reused instead of refetched
+const MAX_AGE: u64 = 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Region {
+    #[name = "EU"]
+    Eu,
+    #[name = "NA"]
+    Na,
+    #[name = "Asia"]
+    Asia,
+}
+
+impl Region {
+    pub fn base_url(&self) -> &'static str {
+        match self {
+            Region::Eu => "https://api.worldoftanks.eu/wot",
+            Region::Na => "https://api.worldoftanks.com/wot",
+            Region::Asia => "https://api.worldoftanks.asia/wot",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "eu" => Some(Region::Eu),
+            "na" | "com" => Some(Region::Na),
+            "asia" => Some(Region::Asia),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    status: String,
+    data: Option<T>,
+    error: Option<ApiError>,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    code: u32,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct AccountEntry {
+    nickname: String,
+    account_id: u64,
+}
+
+#[derive(Deserialize)]
+struct TankEntry {
+    tank_id: u32,
+    all: AllStats,
+}
+
+#[derive(Deserialize)]
+struct AllStats {
+    battles: u64,
+    damage_dealt: u64,
+    frags: u64,
+    spotted: u64,
+    dropped_capture_points: u64,
+    wins: u64,
+}
+
+/// Stats as they were at `fetched_at` (unix seconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub fetched_at: u64,
+    pub tanks: Vec<TankStats>,
+}
+
+pub struct WargamingClient {
+    http: reqwest::Client,
+    base_url: String,
+    application_id: String,
+    cache_dir: Option<PathBuf>,
+}
+
+impl WargamingClient {
+    pub fn new(application_id: String, region: Region) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: region.base_url().to_string(),
+            application_id,
+            cache_dir: None,
+        }
+    }
+
+    /// Overrides the region url, used to point at a mirror or mock server
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Keeps account ids and stat snapshots on disk between runs
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, String)]) -> Result<T, Error> {
+        let response: ApiResponse<T> = self.http
+            .get(format!("{}/{}/", self.base_url, path))
+            .query(&[("application_id", self.application_id.as_str())])
+            .query(query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if response.status != "ok" {
+            return Err(match response.error {
+                Some(e) => format!("Wargaming API error {}: {}", e.code, e.message).into(),
+                None => "Wargaming API error".into(),
+            });
+        }
+        response.data.ok_or_else(|| "Wargaming API returned no data".into())
+    }
+
+    /// Resolves exact nicknames to account ids, unknown nicknames are left out.
+    /// Keys are the nicknames as passed in
+    pub async fn account_ids(&self, nicknames: &[String]) -> Result<HashMap<String, u64>, Error> {
+        let mut cache: HashMap<String, u64> = self.read_cache("accounts.json").unwrap_or_default();
+        let mut ids = HashMap::new();
+        let mut missing = Vec::new();
+        for nickname in nicknames {
+            match cache.get(&nickname.to_lowercase()) {
+                Some(id) => {
+                    ids.insert(nickname.clone(), *id);
+                }
+                None => missing.push(nickname.clone()),
+            }
+        }
+        for chunk in missing.chunks(BATCH_SIZE) {
+            let accounts: Vec<AccountEntry> = self.get("account/list", &[
+                ("type", "exact".to_string()),
+                ("search", chunk.join(",")),
+            ]).await?;
+            for account in accounts {
+                cache.insert(account.nickname.to_lowercase(), account.account_id);
+            }
+            for nickname in chunk {
+                if let Some(id) = cache.get(&nickname.to_lowercase()) {
+                    ids.insert(nickname.clone(), *id);
+                }
+            }
+        }
+        if !missing.is_empty() {
+            self.write_cache("accounts.json", &cache)?;
+        }
+        Ok(ids)
+    }
+
+    /// Current per tank totals, accounts with hidden stats are left out
+    pub async fn tank_stats(&self, account_ids: &[u64]) -> Result<HashMap<u64, Vec<TankStats>>, Error> {
+        let mut stats = HashMap::new();
+        for chunk in account_ids.chunks(BATCH_SIZE) {
+            let ids: Vec<String> = chunk.iter().map(|id| id.to_string()).collect();
+            let data: HashMap<String, Option<Vec<TankEntry>>> = self.get("tanks/stats", &[
+                ("account_id", ids.join(",")),
+                ("fields", "tank_id,all.battles,all.damage_dealt,all.frags,all.spotted,all.dropped_capture_points,all.wins".to_string()),
+            ]).await?;
+            for (id, tanks) in data {
+                let (Ok(id), Some(tanks)) = (id.parse::<u64>(), tanks) else {
+                    continue;
+                };
+                stats.insert(id, tanks.into_iter().map(|t| TankStats {
+                    tank_id: t.tank_id,
+                    battles: t.all.battles,
+                    damage_dealt: t.all.damage_dealt,
+                    frags: t.all.frags,
+                    spotted: t.all.spotted,
+                    dropped_capture_points: t.all.dropped_capture_points,
+                    wins: t.all.wins,
+                }).collect());
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Overall stats plus the battles played in the last `recent_days`, worked
+    /// out from the snapshot history in the cache. Without a snapshot from
+    /// before the window the recent stats are the overall stats and marked as
+    /// unavailable. Snapshots too old to matter are pruned from the cache.
+    pub async fn player_stats(&self, account_ids: &[u64], recent_days: u64) -> Result<HashMap<u64, PlayerStats>, Error> {
+        let now = unix_now();
+        let mut history: HashMap<u64, Vec<Snapshot>> = HashMap::new();
+        let cutoff = now.saturating_sub(recent_days * 24 * 60 * 60);
+        let mut stale = Vec::new();
+        for id in account_ids {
+            let snapshots: Vec<Snapshot> = self.read_cache(&format!("stats_{}.json", id)).unwrap_or_default();
+            match snapshots.last() {
+                Some(last) if now.saturating_sub(last.fetched_at) < MAX_AGE => {}
+                _ => stale.push(*id),
+            }
+            history.insert(*id, snapshots);
+        }
+        for (id, tanks) in self.tank_stats(&stale).await? {
+            let snapshots = history.entry(id).or_default();
+            snapshots.push(Snapshot { fetched_at: now, tanks });
+            prune(snapshots, cutoff);
+            self.write_cache(&format!("stats_{}.json", id), snapshots)?;
+        }
+        let mut stats = HashMap::new();
+        for (id, snapshots) in history {
+            let Some(current) = snapshots.last() else {
+                continue;
+            };
+            let baseline = snapshots.iter()
+                .rev()
+                .find(|s| s.fetched_at <= cutoff)
+                .filter(|s| s.fetched_at < current.fetched_at);
+            let recent = match baseline {
+                Some(old) => diff(&current.tanks, &old.tanks),
+                None => current.tanks.clone(),
+            };
+            stats.insert(id, PlayerStats {
+                overall: current.tanks.clone(),
+                recent,
+                recent_unavailable: baseline.is_none(),
+            });
+        }
+        Ok(stats)
+    }
+
+    fn read_cache<T: DeserializeOwned>(&self, file: &str) -> Option<T> {
+        let path = self.cache_dir.as_ref()?.join(file);
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    fn write_cache<T: Serialize + ?Sized>(&self, file: &str, value: &T) -> Result<(), Error> {
+        if let Some(dir) = &self.cache_dir {
+            std::fs::create_dir_all(dir)?;
+            std::fs::write(dir.join(file), serde_json::to_string(value)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Drops every snapshot before the newest one at or before `cutoff`. That one
+/// is the baseline for the recent stats, nothing older is used again
+pub fn prune(snapshots: &mut Vec<Snapshot>, cutoff: u64) {
+    if let Some(baseline) = snapshots.iter().rposition(|s| s.fetched_at <= cutoff) {
+        snapshots.drain(..baseline);
+    }
+}
+
+/// Battles played between two snapshots, tanks without new battles are dropped
+pub fn diff(current: &[TankStats], old: &[TankStats]) -> Vec<TankStats> {
+    let old: HashMap<u32, &TankStats> = old.iter().map(|t| (t.tank_id, t)).collect();
+    current.iter().filter_map(|t| {
+        let before = old.get(&t.tank_id).copied().copied().unwrap_or(TankStats {
+            tank_id: t.tank_id,
+            ..Default::default()
+        });
+        if t.battles <= before.battles {
+            return None;
+        }
+        Some(TankStats {
+            tank_id: t.tank_id,
+            battles: t.battles - before.battles,
+            damage_dealt: t.damage_dealt.saturating_sub(before.damage_dealt),
+            frags: t.frags.saturating_sub(before.frags),
+            spotted: t.spotted.saturating_sub(before.spotted),
+            dropped_capture_points: t.dropped_capture_points.saturating_sub(before.dropped_capture_points),
+            wins: t.wins.saturating_sub(before.wins),
+        })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers every request on a local port with the body picked by `route`
+    async fn mock_server(route: fn(&str) -> String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let mut buf = vec![0; 8192];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let body = route(request.lines().next().unwrap_or(""));
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body,
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("http://{}/wot", addr)
+    }
+
+    fn route(request_line: &str) -> String {
+        if request_line.contains("/wot/account/list/") {
+            r#"{"status":"ok","meta":{"count":1},"data":[{"nickname":"Zray","account_id":500}]}"#.to_string()
+        } else if request_line.contains("/wot/tanks/stats/") {
+            r#"{"status":"ok","meta":{"count":2},"data":{
+                "500":[{"tank_id":1,"all":{"battles":10,"damage_dealt":9000,"frags":14,"spotted":15,"dropped_capture_points":6,"wins":6}}],
+                "501":null
+            }}"#.to_string()
+        } else {
+            r#"{"status":"error","error":{"code":404,"message":"METHOD_NOT_FOUND"}}"#.to_string()
+        }
+    }
+
+    fn tank(tank_id: u32, battles: u64, wins: u64) -> TankStats {
+        TankStats {
+            tank_id,
+            battles,
+            damage_dealt: battles * 1000,
+            frags: battles,
+            spotted: battles,
+            dropped_capture_points: battles,
+            wins,
+        }
+    }
+
+    #[tokio::test]
+    async fn resolves_nicknames_and_fetches_stats() {
+        let base = mock_server(route).await;
+        let client = WargamingClient::new("test".to_string(), Region::Eu).with_base_url(base);
+        let ids = client.account_ids(&["zray".to_string(), "nobody".to_string()]).await.unwrap();
+        assert_eq!(ids.get("zray"), Some(&500));
+        assert_eq!(ids.get("nobody"), None);
+        let stats = client.tank_stats(&[500, 501]).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[&500], vec![TankStats {
+            tank_id: 1,
+            battles: 10,
+            damage_dealt: 9000,
+            frags: 14,
+            spotted: 15,
+            dropped_capture_points: 6,
+            wins: 6,
+        }]);
+    }
+
+    #[tokio::test]
+    async fn api_errors_are_returned() {
+        let base = mock_server(|_| r#"{"status":"error","error":{"code":407,"message":"INVALID_APPLICATION_ID"}}"#.to_string()).await;
+        let client = WargamingClient::new("bad".to_string(), Region::Na).with_base_url(base);
+        let err = client.account_ids(&["zray".to_string()]).await.unwrap_err();
+        assert!(err.to_string().contains("INVALID_APPLICATION_ID"));
+    }
+
+    #[tokio::test]
+    async fn recent_stats_come_from_cached_snapshots() {
+        let base = mock_server(route).await;
+        let dir = std::env::temp_dir().join(format!("wg_cache_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let old = vec![Snapshot { fetched_at: 0, tanks: vec![tank(1, 4, 2)] }];
+        std::fs::write(dir.join("stats_500.json"), serde_json::to_string(&old).unwrap()).unwrap();
+        let client = WargamingClient::new("test".to_string(), Region::Eu)
+            .with_base_url(base)
+            .with_cache_dir(&dir);
+        let stats = client.player_stats(&[500], 30).await.unwrap();
+        assert_eq!(stats[&500].overall[0].battles, 10);
+        assert_eq!(stats[&500].recent[0].battles, 6);
+        assert_eq!(stats[&500].recent[0].wins, 4);
+        assert!(!stats[&500].recent_unavailable);
+        let cached: Vec<Snapshot> = serde_json::from_str(
+            &std::fs::read_to_string(dir.join("stats_500.json")).unwrap()
+        ).unwrap();
+        assert_eq!(cached.len(), 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn recent_stats_without_history_are_unavailable() {
+        let base = mock_server(route).await;
+        let dir = std::env::temp_dir().join(format!("wg_cache_fresh_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let client = WargamingClient::new("test".to_string(), Region::Eu)
+            .with_base_url(base)
+            .with_cache_dir(&dir);
+        let stats = client.player_stats(&[500], 30).await.unwrap();
+        assert!(stats[&500].recent_unavailable);
+        assert_eq!(stats[&500].recent, stats[&500].overall);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prune_keeps_the_baseline_and_newer() {
+        let snapshot = |fetched_at| Snapshot { fetched_at, tanks: Vec::new() };
+        let mut snapshots = vec![snapshot(10), snapshot(20), snapshot(30), snapshot(40)];
+        prune(&mut snapshots, 25);
+        let kept: Vec<u64> = snapshots.iter().map(|s| s.fetched_at).collect();
+        assert_eq!(kept, vec![20, 30, 40]);
+        prune(&mut snapshots, 5);
+        assert_eq!(snapshots.len(), 3);
+    }
+
+    #[test]
+    fn diff_drops_unplayed_tanks() {
+        let recent = diff(&[tank(1, 10, 6), tank(2, 5, 1), tank(3, 3, 3)], &[tank(1, 4, 2), tank(2, 5, 1)]);
+        assert_eq!(recent, vec![
+            TankStats { tank_id: 1, battles: 6, damage_dealt: 6000, frags: 6, spotted: 6, dropped_capture_points: 6, wins: 4 },
+            tank(3, 3, 3),
+        ]);
+    }
+}
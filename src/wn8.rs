@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::Error;
+
+/// Battle totals for one tank, field names match the Wargaming API
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TankStats {
+    pub tank_id: u32,
+    pub battles: u64,
+    pub damage_dealt: u64,
+    pub frags: u64,
+    pub spotted: u64,
+    pub dropped_capture_points: u64,
+    pub wins: u64,
+}
+
+/// Per battle expected values for one tank, as published in the XVM table
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Expected {
+    #[serde(rename = "IDNum")]
+    pub tank_id: u32,
+    #[serde(rename = "expDamage")]
+    pub damage: f64,
+    #[serde(rename = "expSpot")]
+    pub spot: f64,
+    #[serde(rename = "expFrag")]
+    pub frag: f64,
+    #[serde(rename = "expDef")]
+    pub def: f64,
+    #[serde(rename = "expWinRate")]
+    pub win_rate: f64,
+}
+
+#[derive(Deserialize)]
+struct ExpectedFile {
+    data: Vec<Expected>,
+}
+
+pub struct ExpectedValues {
+    table: HashMap<u32, Expected>,
+}
+
+impl ExpectedValues {
+    pub fn new(values: Vec<Expected>) -> Self {
+        Self {
+            table: values.into_iter().map(|e| (e.tank_id, e)).collect(),
+        }
+    }
+
+    /// Parses the `{"header": .., "data": [..]}` expected values json
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let file: ExpectedFile = serde_json::from_str(json)?;
+        Ok(Self::new(file.data))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn get(&self, tank_id: u32) -> Option<&Expected> {
+        self.table.get(&tank_id)
+    }
+
+    /// Account WN8 over a set of tanks, tanks missing from the table are skipped
+    pub fn calculate(&self, stats: &[TankStats]) -> f64 {
+        let mut actual = Totals::default();
+        let mut expected = Totals::default();
+        for tank in stats {
+            let Some(exp) = self.get(tank.tank_id) else {
+                continue;
+            };
+            let battles = tank.battles as f64;
+            actual.damage += tank.damage_dealt as f64;
+            actual.spot += tank.spotted as f64;
+            actual.frag += tank.frags as f64;
+            actual.def += tank.dropped_capture_points as f64;
+            actual.win_rate += tank.wins as f64 * 100.0;
+            expected.damage += exp.damage * battles;
+            expected.spot += exp.spot * battles;
+            expected.frag += exp.frag * battles;
+            expected.def += exp.def * battles;
+            expected.win_rate += exp.win_rate * battles;
+        }
+        if expected.damage == 0.0 {
+            return 0.0;
+        }
+        formula(
+            actual.damage / expected.damage,
+            actual.spot / expected.spot,
+            actual.frag / expected.frag,
+            actual.def / expected.def,
+            actual.win_rate / expected.win_rate,
+        )
+    }
+}
+
+#[derive(Default)]
+struct Totals {
+    damage: f64,
+    spot: f64,
+    frag: f64,
+    def: f64,
+    win_rate: f64,
+}
+
+/// The WN8 formula applied to the actual/expected ratios
+pub fn formula(r_damage: f64, r_spot: f64, r_frag: f64, r_def: f64, r_win: f64) -> f64 {
+    let r_win_c = ((r_win - 0.71) / (1.0 - 0.71)).max(0.0);
+    let r_damage_c = ((r_damage - 0.22) / (1.0 - 0.22)).max(0.0);
+    let r_frag_c = ((r_frag - 0.12) / (1.0 - 0.12)).min(r_damage_c + 0.2).max(0.0);
+    let r_spot_c = ((r_spot - 0.38) / (1.0 - 0.38)).min(r_damage_c + 0.1).max(0.0);
+    let r_def_c = ((r_def - 0.10) / (1.0 - 0.10)).min(r_damage_c + 0.1).max(0.0);
+    980.0 * r_damage_c
+        + 210.0 * r_damage_c * r_frag_c
+        + 155.0 * r_frag_c * r_spot_c
+        + 75.0 * r_def_c * r_frag_c
+        + 145.0 * r_win_c.min(1.8)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerStats {
+    pub overall: Vec<TankStats>,
+    pub recent: Vec<TankStats>,
+    /// Not enough history for the recent window, `recent` is the overall stats
+    pub recent_unavailable: bool,
+}
+
+#[derive(Deserialize)]
+struct StatsRecord {
+    name: String,
+    period: String,
+    tank_id: u32,
+    battles: u64,
+    damage_dealt: u64,
+    frags: u64,
+    spotted: u64,
+    dropped_capture_points: u64,
+    wins: u64,
+}
+
+/// Reads per tank stats, columns are name, period (overall or recent) and the
+/// `TankStats` fields
+pub fn load_tank_stats(path: impl AsRef<Path>) -> Result<HashMap<String, PlayerStats>, Error> {
+    let mut rdr = csv::Reader::from_path(path)?;
+    let mut stats: HashMap<String, PlayerStats> = HashMap::new();
+    for result in rdr.deserialize() {
+        let record: StatsRecord = result?;
+        let tank = TankStats {
+            tank_id: record.tank_id,
+            battles: record.battles,
+            damage_dealt: record.damage_dealt,
+            frags: record.frags,
+            spotted: record.spotted,
+            dropped_capture_points: record.dropped_capture_points,
+            wins: record.wins,
+        };
+        let entry = stats.entry(record.name).or_default();
+        match record.period.as_str() {
+            "recent" => entry.recent.push(tank),
+            _ => entry.overall.push(tank),
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> ExpectedValues {
+        ExpectedValues::from_json(r#"{
+            "header": {"version": "test"},
+            "data": [
+                {"IDNum": 1, "expDef": 0.5, "expFrag": 1.1, "expSpot": 1.3, "expDamage": 600.0, "expWinRate": 55.0},
+                {"IDNum": 2, "expDef": 0.7, "expFrag": 0.9, "expSpot": 1.0, "expDamage": 1200.0, "expWinRate": 50.0}
+            ]
+        }"#).unwrap()
+    }
+
+    fn tank(tank_id: u32, battles: u64, damage: u64, spotted: u64, frags: u64, def: u64, wins: u64) -> TankStats {
+        TankStats {
+            tank_id,
+            battles,
+            damage_dealt: damage,
+            frags,
+            spotted,
+            dropped_capture_points: def,
+            wins,
+        }
+    }
+
+    #[test]
+    fn expected_performance_is_1565() {
+        let wn8 = table().calculate(&[tank(1, 100, 60000, 130, 110, 50, 55)]);
+        assert!((wn8 - 1565.0).abs() < 1e-6, "{}", wn8);
+    }
+
+    #[test]
+    fn single_tank_reference() {
+        let wn8 = table().calculate(&[tank(1, 100, 90000, 150, 140, 60, 60)]);
+        assert!((wn8 - 2623.5717).abs() < 1e-3, "{}", wn8);
+    }
+
+    #[test]
+    fn weights_by_battles_across_tanks() {
+        let wn8 = table().calculate(&[
+            tank(1, 100, 90000, 150, 140, 60, 60),
+            tank(2, 50, 50000, 40, 40, 30, 24),
+        ]);
+        assert!((wn8 - 1955.3779).abs() < 1e-3, "{}", wn8);
+    }
+
+    #[test]
+    fn unknown_tanks_are_skipped() {
+        let wn8 = table().calculate(&[
+            tank(1, 100, 60000, 130, 110, 50, 55),
+            tank(99, 1000, 0, 0, 0, 0, 0),
+        ]);
+        assert!((wn8 - 1565.0).abs() < 1e-6, "{}", wn8);
+    }
+
+    #[test]
+    fn no_battles_is_zero() {
+        assert_eq!(table().calculate(&[]), 0.0);
+        assert_eq!(formula(0.1, 0.1, 0.1, 0.1, 0.1), 0.0);
+    }
+
+    #[test]
+    fn win_rate_component_is_capped() {
+        let wn8 = formula(3.0, 3.0, 3.0, 3.0, 3.0);
+        assert!((wn8 - 8852.9394).abs() < 1e-3, "{}", wn8);
+    }
+}